@@ -0,0 +1,122 @@
+use crate::i18n;
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, CssProvider, Orientation, ToggleButton, Widget, accessible::Property, gdk};
+use std::thread::LocalKey;
+
+/// `[accessibility]` settings.
+#[derive(Clone, Debug)]
+pub struct Accessibility {
+    /// `[accessibility] animations`, off stills the bounce, typing, fades and shakes
+    pub animations: bool,
+    /// `[accessibility] high_contrast`, start with the high-contrast style
+    pub high_contrast: bool,
+    /// `[accessibility] large_text`, start with the large-text style
+    pub large_text: bool,
+    /// `[accessibility] toggles`, buttons switching both styles
+    pub toggles: bool,
+}
+
+impl Default for Accessibility {
+    fn default() -> Self {
+        Self {
+            animations: true,
+            high_contrast: false,
+            large_text: false,
+            toggles: true,
+        }
+    }
+}
+
+const HIGH_CONTRAST: &str = include_str!("high-contrast.css");
+const LARGE_TEXT: &str = include_str!("large-text.css");
+
+thread_local! {
+    static HIGH_CONTRAST_PROVIDER: CssProvider = provider(HIGH_CONTRAST);
+    static LARGE_TEXT_PROVIDER: CssProvider = provider(LARGE_TEXT);
+}
+
+fn provider(css: &str) -> CssProvider {
+    let provider = CssProvider::new();
+    provider.load_from_data(css);
+    provider
+}
+
+/// Adds or removes one of the styles, above any theme.
+fn set_style(provider: &CssProvider, on: bool) {
+    let Some(display) = gdk::Display::default() else {
+        return;
+    };
+    if on {
+        gtk4::style_context_add_provider_for_display(&display, provider, gtk4::STYLE_PROVIDER_PRIORITY_USER);
+    } else {
+        gtk4::style_context_remove_provider_for_display(&display, provider);
+    }
+}
+
+/// Applies the configured styles, and turns GTK's own animations off if asked to.
+pub fn apply(accessibility: &Accessibility) {
+    if !accessibility.animations
+        && let Some(settings) = gtk4::Settings::default()
+    {
+        settings.set_gtk_enable_animations(false);
+    }
+    if accessibility.high_contrast {
+        HIGH_CONTRAST_PROVIDER.with(|p| set_style(p, true));
+    }
+    if accessibility.large_text {
+        LARGE_TEXT_PROVIDER.with(|p| set_style(p, true));
+    }
+}
+
+/// Gives `widget` the accessible name `id`, kept translated.
+pub fn label(widget: &impl IsA<Widget>, id: &'static str) {
+    let widget = widget.upcast_ref::<Widget>().downgrade();
+    i18n::on_change(move || {
+        if let Some(widget) = widget.upgrade() {
+            widget.update_property(&[Property::Label(&i18n::tr(id))]);
+        }
+    });
+}
+
+/// Gives `widget` the accessible description `id`, kept translated.
+pub fn description(widget: &impl IsA<Widget>, id: &'static str) {
+    let widget = widget.upcast_ref::<Widget>().downgrade();
+    i18n::on_change(move || {
+        if let Some(widget) = widget.upgrade() {
+            widget.update_property(&[Property::Description(&i18n::tr(id))]);
+        }
+    });
+}
+
+fn toggle(text: &str, id: &'static str, active: bool, style: &'static LocalKey<CssProvider>) -> ToggleButton {
+    let button = ToggleButton::with_label(text);
+    button.add_css_class("a11y-toggle");
+    button.set_active(active);
+    {
+        let button = button.downgrade();
+        i18n::on_change(move || {
+            if let Some(button) = button.upgrade() {
+                let name = i18n::tr(id);
+                button.set_tooltip_text(Some(&name));
+                button.update_property(&[Property::Label(&name)]);
+            }
+        });
+    }
+    button.connect_toggled(move |button| {
+        let on = button.is_active();
+        style.with(|p| set_style(p, on));
+    });
+    button
+}
+
+/// High-contrast and large-text switches. `None` if `[accessibility] toggles` is off.
+pub fn toggles(accessibility: &Accessibility) -> Option<GtkBox> {
+    if !accessibility.toggles {
+        return None;
+    }
+    let toggles = GtkBox::new(Orientation::Horizontal, 4);
+    toggles.set_widget_name("a11y-toggles");
+    toggles.append(&toggle("◐", "high_contrast", accessibility.high_contrast, &HIGH_CONTRAST_PROVIDER));
+    toggles.append(&toggle("A⁺", "large_text", accessibility.large_text, &LARGE_TEXT_PROVIDER));
+    Some(toggles)
+}
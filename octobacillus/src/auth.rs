@@ -0,0 +1,362 @@
+use crate::{i18n, lockout::Lockout};
+use greetd_ipc::{AuthMessageType, ErrorType, Request, Response};
+use std::time::{Duration, Instant};
+
+/// What the user typed in, plus the session to start with it.
+#[derive(Clone, Debug)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+    pub cmd: Vec<String>,
+    pub env: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum State {
+    Idle,
+    CreatingSession,
+    Prompting,
+    Starting,
+    Failed,
+    LockedOut,
+}
+
+/// Something the front-end should show.
+#[derive(Debug, PartialEq)]
+pub enum UiEvent {
+    /// An info or error message from PAM that should be shown as-is.
+    Message(String),
+    /// greetd rejected the credentials.
+    WrongPassword { attempts_left: u32 },
+    /// greetd rejected the credentials one time too many.
+    LockedOut(Duration),
+    /// Anything else that went wrong: socket, codec or greetd errors.
+    Error(String),
+    /// The session was started, the greeter can go away.
+    Started,
+    /// The conversation was cancelled on request.
+    Cancelled,
+}
+
+impl UiEvent {
+    /// The status text front-ends show for this event.
+    pub fn describe(&self) -> String {
+        match self {
+            UiEvent::Message(text) | UiEvent::Error(text) => text.clone(),
+            UiEvent::WrongPassword { attempts_left } => i18n::trf("wrong_password", &[("n", attempts_left)]),
+            UiEvent::LockedOut(delay) => {
+                i18n::trf("locked_out", &[("n", &(delay.as_secs_f64().ceil() as u64))])
+            }
+            UiEvent::Started | UiEvent::Cancelled => String::new(),
+        }
+    }
+}
+
+/// Why `AuthFlow::login` did not start a conversation.
+#[derive(Debug, PartialEq)]
+pub enum Refused {
+    Busy,
+    LockedOut(Duration),
+}
+
+/// The greetd conversation as a state machine, without any I/O.
+///
+/// Front-ends call `login`, write the returned request, and feed every
+/// response to `handle` until it stops returning requests.
+pub struct AuthFlow {
+    state: State,
+    lockout: Lockout,
+    creds: Option<Credentials>,
+    cancel: bool,
+}
+
+impl AuthFlow {
+    pub fn new(lockout: Lockout) -> Self {
+        Self {
+            state: State::Idle,
+            lockout,
+            creds: None,
+            cancel: false,
+        }
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// Whether a conversation is running.
+    pub fn busy(&self) -> bool {
+        matches!(self.state, State::CreatingSession | State::Prompting | State::Starting)
+    }
+
+    /// Time left in the current lockout. Leaves `LockedOut` once it is over.
+    pub fn locked_out(&mut self, now: Instant) -> Option<Duration> {
+        let remaining = self.lockout.remaining(now);
+        if remaining.is_none() && self.state == State::LockedOut {
+            self.state = State::Idle;
+        }
+        remaining
+    }
+
+    /// Starts a conversation, returning the first request to send.
+    pub fn login(&mut self, creds: Credentials, now: Instant) -> Result<Request, Refused> {
+        if self.busy() {
+            return Err(Refused::Busy);
+        }
+        if let Some(remaining) = self.locked_out(now) {
+            return Err(Refused::LockedOut(remaining));
+        }
+
+        let request = Request::CreateSession { username: creds.username.clone() };
+        self.creds = Some(creds);
+        self.cancel = false;
+        self.state = State::CreatingSession;
+        Ok(request)
+    }
+
+    /// Asks to cancel the running conversation with the next response. Too late
+    /// once the session is being started.
+    pub fn cancel(&mut self) {
+        if matches!(self.state, State::CreatingSession | State::Prompting) {
+            self.cancel = true;
+        }
+    }
+
+    /// Feeds a response from greetd, returning the next request (if any) and what to show.
+    pub fn handle(&mut self, response: Response, now: Instant) -> (Option<Request>, Vec<UiEvent>) {
+        if !self.busy() {
+            // The answer to our own CancelSession, the conversation is over.
+            return (None, Vec::new());
+        }
+        if self.cancel && self.state != State::Starting {
+            // A wrong password still counts, or cancelling would dodge the lockout.
+            let event = match response {
+                Response::Error { error_type: ErrorType::AuthError, .. } => self.wrong_password(now),
+                _ => {
+                    self.finish(State::Idle);
+                    UiEvent::Cancelled
+                }
+            };
+            return (Some(Request::CancelSession), vec![event]);
+        }
+
+        match response {
+            Response::AuthMessage { auth_message, auth_message_type } => {
+                let creds = self.creds.as_ref().expect("busy without credentials");
+                let mut events = Vec::new();
+                let response = match auth_message_type {
+                    AuthMessageType::Visible => Some(creds.username.clone()),
+                    AuthMessageType::Secret => Some(creds.password.clone()),
+                    AuthMessageType::Info | AuthMessageType::Error => {
+                        events.push(UiEvent::Message(auth_message));
+                        None
+                    }
+                };
+                self.state = State::Prompting;
+                (Some(Request::PostAuthMessageResponse { response }), events)
+            }
+            Response::Success if self.state == State::Starting => {
+                self.lockout.record_success();
+                self.creds = None;
+                (None, vec![UiEvent::Started])
+            }
+            Response::Success => {
+                let creds = self.creds.as_ref().expect("busy without credentials");
+                let request = Request::StartSession {
+                    env: creds.env.clone(),
+                    cmd: creds.cmd.clone(),
+                };
+                self.state = State::Starting;
+                (Some(request), Vec::new())
+            }
+            Response::Error { error_type: ErrorType::AuthError, .. } => {
+                (Some(Request::CancelSession), vec![self.wrong_password(now)])
+            }
+            Response::Error { error_type: ErrorType::Error, description } => {
+                self.finish(State::Failed);
+                (Some(Request::CancelSession), vec![UiEvent::Error(description)])
+            }
+        }
+    }
+
+    /// Ends the conversation after the transport failed.
+    pub fn io_error(&mut self, error: String) -> UiEvent {
+        self.finish(State::Failed);
+        UiEvent::Error(error)
+    }
+
+    fn wrong_password(&mut self, now: Instant) -> UiEvent {
+        match self.lockout.record_failure(now) {
+            Some(delay) => {
+                self.finish(State::LockedOut);
+                UiEvent::LockedOut(delay)
+            }
+            None => {
+                self.finish(State::Failed);
+                UiEvent::WrongPassword { attempts_left: self.lockout.attempts_left() }
+            }
+        }
+    }
+
+    fn finish(&mut self, state: State) {
+        self.state = state;
+        self.creds = None;
+        self.cancel = false;
+    }
+}
+
+/// A flow locking out after `max_attempts`, for the tests driving a login.
+#[cfg(test)]
+pub fn test_flow(max_attempts: u32) -> AuthFlow {
+    AuthFlow::new(Lockout::new(max_attempts, Duration::from_secs(30), Duration::from_secs(600)))
+}
+
+/// Credentials for a test login with `password`.
+#[cfg(test)]
+pub fn test_creds(password: &str) -> Credentials {
+    Credentials {
+        username: "ekah".to_string(),
+        password: password.to_string(),
+        cmd: vec!["niri-session".to_string()],
+        env: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secret() -> Response {
+        Response::AuthMessage {
+            auth_message_type: AuthMessageType::Secret,
+            auth_message: "Password:".to_string(),
+        }
+    }
+
+    fn auth_error() -> Response {
+        Response::Error {
+            error_type: ErrorType::AuthError,
+            description: "pam_authenticate: AUTH_ERR".to_string(),
+        }
+    }
+
+    #[test]
+    fn success() {
+        let now = Instant::now();
+        let mut flow = test_flow(3);
+
+        let request = flow.login(test_creds("hunter2"), now).expect("idle flow refused login");
+        assert!(matches!(request, Request::CreateSession { username } if username == "ekah"));
+        assert_eq!(flow.state(), State::CreatingSession);
+
+        let (request, events) = flow.handle(secret(), now);
+        assert!(matches!(
+            request,
+            Some(Request::PostAuthMessageResponse { response: Some(p) }) if p == "hunter2"
+        ));
+        assert!(events.is_empty());
+        assert_eq!(flow.state(), State::Prompting);
+
+        let (request, _) = flow.handle(Response::Success, now);
+        assert!(matches!(request, Some(Request::StartSession { .. })));
+        assert_eq!(flow.state(), State::Starting);
+        assert!(matches!(flow.login(test_creds("hunter2"), now), Err(Refused::Busy)));
+
+        let (request, events) = flow.handle(Response::Success, now);
+        assert!(request.is_none());
+        assert_eq!(events, [UiEvent::Started]);
+    }
+
+    #[test]
+    fn info_message() {
+        let now = Instant::now();
+        let mut flow = test_flow(3);
+        flow.login(test_creds("hunter2"), now).expect("idle flow refused login");
+
+        let info = Response::AuthMessage {
+            auth_message_type: AuthMessageType::Info,
+            auth_message: "Last login: yesterday".to_string(),
+        };
+        let (request, events) = flow.handle(info, now);
+        assert!(matches!(request, Some(Request::PostAuthMessageResponse { response: None })));
+        assert_eq!(events, [UiEvent::Message("Last login: yesterday".to_string())]);
+    }
+
+    #[test]
+    fn wrong_password_then_lockout() {
+        let now = Instant::now();
+        let mut flow = test_flow(2);
+
+        flow.login(test_creds("hunter2"), now).expect("idle flow refused login");
+        flow.handle(secret(), now);
+        let (request, events) = flow.handle(auth_error(), now);
+        assert!(matches!(request, Some(Request::CancelSession)));
+        assert_eq!(events, [UiEvent::WrongPassword { attempts_left: 1 }]);
+        assert_eq!(flow.state(), State::Failed);
+
+        // The reply to CancelSession ends the conversation.
+        let (request, events) = flow.handle(Response::Success, now);
+        assert!(request.is_none());
+        assert!(events.is_empty());
+
+        flow.login(test_creds("hunter2"), now).expect("failed flow refused login");
+        flow.handle(secret(), now);
+        let (_, events) = flow.handle(auth_error(), now);
+        assert_eq!(events, [UiEvent::LockedOut(Duration::from_secs(30))]);
+        assert_eq!(flow.state(), State::LockedOut);
+        assert!(matches!(
+            flow.login(test_creds("hunter2"), now),
+            Err(Refused::LockedOut(d)) if d == Duration::from_secs(30)
+        ));
+
+        assert_eq!(flow.locked_out(now + Duration::from_secs(30)), None);
+        assert_eq!(flow.state(), State::Idle);
+    }
+
+    #[test]
+    fn cancel() {
+        let now = Instant::now();
+        let mut flow = test_flow(3);
+
+        flow.cancel();
+        flow.login(test_creds("hunter2"), now).expect("idle flow refused login");
+        flow.cancel();
+        let (request, events) = flow.handle(secret(), now);
+        assert!(matches!(request, Some(Request::CancelSession)));
+        assert_eq!(events, [UiEvent::Cancelled]);
+        assert_eq!(flow.state(), State::Idle);
+
+        let (request, _) = flow.handle(Response::Success, now);
+        assert!(request.is_none());
+
+        // Once StartSession is sent, the session starts anyway.
+        flow.login(test_creds("hunter2"), now).expect("idle flow refused login");
+        flow.handle(secret(), now);
+        let (request, _) = flow.handle(Response::Success, now);
+        assert!(matches!(request, Some(Request::StartSession { .. })));
+        flow.cancel();
+        let (request, events) = flow.handle(Response::Success, now);
+        assert!(request.is_none());
+        assert_eq!(events, [UiEvent::Started]);
+    }
+
+    #[test]
+    fn cancel_after_wrong_password() {
+        let now = Instant::now();
+        let mut flow = test_flow(2);
+
+        flow.login(test_creds("hunter2"), now).expect("idle flow refused login");
+        flow.handle(secret(), now);
+        flow.cancel();
+        let (request, events) = flow.handle(auth_error(), now);
+        assert!(matches!(request, Some(Request::CancelSession)));
+        assert_eq!(events, [UiEvent::WrongPassword { attempts_left: 1 }]);
+
+        flow.login(test_creds("hunter2"), now).expect("failed flow refused login");
+        flow.handle(secret(), now);
+        flow.cancel();
+        let (_, events) = flow.handle(auth_error(), now);
+        assert!(matches!(events[..], [UiEvent::LockedOut(_)]));
+        assert_eq!(flow.state(), State::LockedOut);
+    }
+}
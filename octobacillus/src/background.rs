@@ -0,0 +1,284 @@
+use crate::{
+    config::{BackgroundMode, Config, Scaling},
+    idle, logging,
+};
+use gtk4::prelude::*;
+use gtk4::{
+    DrawingArea, cairo, gdk,
+    gdk_pixbuf::{Pixbuf, PixbufAnimation, PixbufAnimationIter},
+    glib,
+};
+use std::{
+    cell::RefCell,
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// `[background]` settings.
+#[derive(Clone, Debug)]
+pub struct Background {
+    /// `[paths] background`, a still or animated image
+    pub image: PathBuf,
+    /// `[background] mode`
+    pub mode: BackgroundMode,
+    /// `[background] scaling`
+    pub scaling: Scaling,
+    /// `[background] directory`, the slideshow's pictures
+    pub directory: PathBuf,
+    /// `[background] interval` in seconds between slideshow pictures
+    pub interval: Duration,
+    /// `[background] crossfade` in milliseconds
+    pub crossfade: Duration,
+    /// `[background] color`, any CSS color
+    pub color: String,
+    /// `[background] gradient`, the color `color` fades into, solid if unset
+    pub gradient: Option<String>,
+    /// `[background] angle` of the gradient in degrees, CSS style: 0 points up, 180 down
+    pub angle: f64,
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Self {
+            image: PathBuf::from("/usr/share/octobacillus/bg.png"),
+            mode: BackgroundMode::Image,
+            scaling: Scaling::Stretch,
+            directory: PathBuf::from("/usr/share/octobacillus/backgrounds"),
+            interval: Duration::from_secs(300),
+            crossfade: Duration::from_millis(1000),
+            color: "#000000".to_string(),
+            gradient: None,
+            angle: 180.0,
+        }
+    }
+}
+
+/// What is currently painted, plus the picture being faded out in a slideshow.
+#[derive(Default)]
+struct Canvas {
+    current: Option<Pixbuf>,
+    previous: Option<Pixbuf>,
+    fade_start: Option<Instant>,
+}
+
+/// Scale factors and offset that place an `image`-sized picture in `area`.
+fn placement(scaling: Scaling, image: (f64, f64), area: (f64, f64)) -> (f64, f64, f64, f64) {
+    let (iw, ih) = image;
+    let (w, h) = area;
+    let (sx, sy) = match scaling {
+        Scaling::Stretch => (w / iw, h / ih),
+        Scaling::Fill => {
+            let s = (w / iw).max(h / ih);
+            (s, s)
+        }
+        Scaling::Fit => {
+            let s = (w / iw).min(h / ih);
+            (s, s)
+        }
+        Scaling::Center | Scaling::Tile => (1.0, 1.0),
+    };
+    (sx, sy, (w - iw * sx) / 2.0, (h - ih * sy) / 2.0)
+}
+
+fn paint_pixbuf(cr: &cairo::Context, pixbuf: &Pixbuf, scaling: Scaling, area: (f64, f64), alpha: f64) {
+    let _ = cr.save();
+    if scaling == Scaling::Tile {
+        cr.set_source_pixbuf(pixbuf, 0.0, 0.0);
+        cr.source().set_extend(cairo::Extend::Repeat);
+    } else {
+        let image = (pixbuf.width() as f64, pixbuf.height() as f64);
+        let (sx, sy, x, y) = placement(scaling, image, area);
+        cr.translate(x, y);
+        cr.scale(sx, sy);
+        cr.set_source_pixbuf(pixbuf, 0.0, 0.0);
+    }
+    let _ = cr.paint_with_alpha(alpha);
+    let _ = cr.restore();
+}
+
+fn parse_color(color: &str) -> gdk::RGBA {
+    gdk::RGBA::parse(color).unwrap_or_else(|_| {
+        logging::warning(&format!("bad background color {color}, using black"), &[]);
+        gdk::RGBA::BLACK
+    })
+}
+
+/// Paints a solid color, or a CSS-like linear gradient at `angle` degrees (0 points up).
+fn paint_color(cr: &cairo::Context, from: &gdk::RGBA, to: Option<&gdk::RGBA>, angle: f64, (w, h): (f64, f64)) {
+    let Some(to) = to else {
+        cr.set_source_rgba(from.red().into(), from.green().into(), from.blue().into(), from.alpha().into());
+        let _ = cr.paint();
+        return;
+    };
+
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let half = ((w * sin).abs() + (h * cos).abs()) / 2.0;
+    let (cx, cy) = (w / 2.0, h / 2.0);
+    let gradient = cairo::LinearGradient::new(cx - sin * half, cy + cos * half, cx + sin * half, cy - cos * half);
+    for (offset, c) in [(0.0, from), (1.0, to)] {
+        gradient.add_color_stop_rgba(offset, c.red().into(), c.green().into(), c.blue().into(), c.alpha().into());
+    }
+    let _ = cr.set_source(&gradient);
+    let _ = cr.paint();
+}
+
+fn slideshow_images(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        logging::error(&format!("cannot read slideshow directory {}", dir.display()), &[]);
+        return Vec::new();
+    };
+    let mut images: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| matches!(e.to_ascii_lowercase().as_str(), "png" | "jpg" | "jpeg" | "webp" | "gif" | "bmp"))
+        })
+        .collect();
+    images.sort();
+    images
+}
+
+/// Shows the frames of an animated image, each for as long as it asks.
+fn animate(area: &DrawingArea, canvas: &Rc<RefCell<Canvas>>, iter: PixbufAnimationIter) {
+    let Some(delay) = iter.delay_time() else {
+        return; // The last frame stays forever.
+    };
+    let area = area.downgrade();
+    let canvas = canvas.clone();
+    glib::timeout_add_local_once(delay, move || {
+        let Some(area) = area.upgrade() else {
+            return;
+        };
+        // Idle, the frame on screen stays; advancing later skips what was missed.
+        if !idle::paused() {
+            iter.advance(SystemTime::now());
+            canvas.borrow_mut().current = Some(iter.pixbuf());
+            area.queue_draw();
+        }
+        animate(&area, &canvas, iter);
+    });
+}
+
+fn start_slideshow(area: &DrawingArea, canvas: &Rc<RefCell<Canvas>>, background: &Background) {
+    let images = slideshow_images(&background.directory);
+    let mut index = 0;
+    let mut next = move || {
+        for _ in 0..images.len() {
+            let path = &images[index % images.len()];
+            index += 1;
+            match Pixbuf::from_file(path) {
+                Ok(pixbuf) => return Some(pixbuf),
+                Err(e) => logging::error(&format!("{}: {e}", path.display()), &[]),
+            }
+        }
+        None
+    };
+
+    canvas.borrow_mut().current = next();
+    let crossfade = background.crossfade;
+    let area_weak = area.downgrade();
+    let canvas = canvas.clone();
+    glib::timeout_add_local(background.interval.max(Duration::from_secs(1)), move || {
+        let Some(area) = area_weak.upgrade() else {
+            return glib::ControlFlow::Break;
+        };
+        if idle::paused() {
+            return glib::ControlFlow::Continue;
+        }
+        let Some(pixbuf) = next() else {
+            return glib::ControlFlow::Continue;
+        };
+        {
+            let mut canvas = canvas.borrow_mut();
+            canvas.previous = canvas.current.replace(pixbuf);
+            canvas.fade_start = Some(Instant::now());
+        }
+
+        let canvas = canvas.clone();
+        area.add_tick_callback(move |area, _| {
+            let mut canvas = canvas.borrow_mut();
+            area.queue_draw();
+            if canvas.fade_start.is_some_and(|start| start.elapsed() < crossfade) {
+                return glib::ControlFlow::Continue;
+            }
+            canvas.previous = None;
+            canvas.fade_start = None;
+            glib::ControlFlow::Break
+        });
+        glib::ControlFlow::Continue
+    });
+}
+
+/// The full-screen background, as configured in `[background]`.
+pub fn widget(config: &Config) -> DrawingArea {
+    let area = DrawingArea::new();
+    area.set_widget_name("gif-bg");
+    area.set_hexpand(true);
+    area.set_vexpand(true);
+
+    let background = &config.background;
+    let canvas = Rc::new(RefCell::new(Canvas::default()));
+    match background.mode {
+        BackgroundMode::Image => match PixbufAnimation::from_file(&background.image) {
+            Ok(animation) if animation.is_static_image() => {
+                canvas.borrow_mut().current = animation.static_image();
+            }
+            Ok(animation) => {
+                let iter = animation.iter(Some(SystemTime::now()));
+                canvas.borrow_mut().current = Some(iter.pixbuf());
+                animate(&area, &canvas, iter);
+            }
+            Err(e) => logging::error(&format!("{}: {e}", background.image.display()), &[]),
+        },
+        BackgroundMode::Slideshow => start_slideshow(&area, &canvas, background),
+        BackgroundMode::Color => {}
+    }
+
+    let mode = background.mode;
+    let scaling = background.scaling;
+    let crossfade = background.crossfade.as_secs_f64().max(f64::EPSILON);
+    let color = parse_color(&background.color);
+    let gradient = background.gradient.as_deref().map(parse_color);
+    let angle = background.angle;
+
+    area.set_draw_func(move |_, cr, w, h| {
+        let size = (w as f64, h as f64);
+        if mode == BackgroundMode::Color {
+            paint_color(cr, &color, gradient.as_ref(), angle, size);
+            return;
+        }
+
+        let canvas = canvas.borrow();
+        let t = canvas
+            .fade_start
+            .map_or(1.0, |start| (start.elapsed().as_secs_f64() / crossfade).min(1.0));
+        // The new picture fades in over the old one, so nothing shows through.
+        if let Some(previous) = &canvas.previous {
+            paint_pixbuf(cr, previous, scaling, size, 1.0);
+        }
+        if let Some(current) = &canvas.current {
+            paint_pixbuf(cr, current, scaling, size, t);
+        }
+    });
+
+    area
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placements() {
+        let image = (100.0, 50.0);
+        let area = (400.0, 400.0);
+
+        assert_eq!(placement(Scaling::Stretch, image, area), (4.0, 8.0, 0.0, 0.0));
+        assert_eq!(placement(Scaling::Fill, image, area), (8.0, 8.0, -200.0, 0.0));
+        assert_eq!(placement(Scaling::Fit, image, area), (4.0, 4.0, 0.0, 100.0));
+        assert_eq!(placement(Scaling::Center, image, area), (1.0, 1.0, 150.0, 175.0));
+    }
+}
@@ -0,0 +1,97 @@
+use crate::{
+    i18n,
+    issue::{self, Host},
+    logging,
+};
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Button, Label, Orientation, Widget};
+use std::{fs, io, path::PathBuf};
+
+/// `[banner]` settings.
+#[derive(Clone, Debug, Default)]
+pub struct Banner {
+    /// `[banner] issue`, an `/etc/issue`-style file, escapes filled in
+    pub issue: Option<PathBuf>,
+    /// `[banner] motd`, a message of the day shown as it is
+    pub motd: Option<PathBuf>,
+    /// `[banner] must_acknowledge`, no login until the banner was accepted
+    pub must_acknowledge: bool,
+}
+
+fn read(path: &PathBuf) -> Option<String> {
+    match fs::read_to_string(path) {
+        Ok(s) => Some(s.trim_end().to_string()).filter(|s| !s.is_empty()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => {
+            logging::error(&format!("{}: {e}", path.display()), &[]);
+            None
+        }
+    }
+}
+
+/// The issue and the message of the day, `None` if there is neither.
+pub fn text(banner: &Banner) -> Option<String> {
+    let issue = banner
+        .issue
+        .as_ref()
+        .and_then(read)
+        .map(|issue| issue::expand(&issue, &Host::current()));
+    let motd = banner.motd.as_ref().and_then(read);
+    match (issue, motd) {
+        (Some(issue), Some(motd)) => Some(format!("{issue}\n\n{motd}")),
+        (issue, motd) => issue.or(motd),
+    }
+}
+
+/// Puts the banner at the top of `parent`, if there is one. With `must_acknowledge`,
+/// `locked` stays insensitive until it is accepted, then the first visible one
+/// of them gets the focus.
+pub fn show(banner: &Banner, parent: &GtkBox, locked: &[Widget]) {
+    let Some(text) = text(banner) else {
+        return;
+    };
+
+    let container = GtkBox::new(Orientation::Vertical, 10);
+    container.set_widget_name("banner");
+    container.set_halign(gtk4::Align::Center);
+
+    let label = Label::new(Some(&text));
+    label.set_wrap(true);
+    label.set_justify(gtk4::Justification::Center);
+    label.set_max_width_chars(80);
+    container.append(&label);
+    parent.prepend(&container);
+
+    if !banner.must_acknowledge {
+        return;
+    }
+
+    logging::info("waiting for the banner to be acknowledged", &[]);
+    for widget in locked {
+        widget.set_sensitive(false);
+    }
+    let accept = Button::new();
+    accept.set_widget_name("acknowledge");
+    accept.set_halign(gtk4::Align::Center);
+    {
+        let accept = accept.downgrade();
+        i18n::on_change(move || {
+            if let Some(accept) = accept.upgrade() {
+                accept.set_label(&i18n::tr("acknowledge"));
+            }
+        });
+    }
+    let locked = locked.to_vec();
+    accept.connect_clicked(move |accept| {
+        logging::info("banner acknowledged", &[]);
+        accept.set_visible(false);
+        for widget in &locked {
+            widget.set_sensitive(true);
+        }
+        if let Some(widget) = locked.iter().find(|widget| widget.is_visible()) {
+            widget.grab_focus();
+        }
+    });
+    container.append(&accept);
+    accept.grab_focus();
+}
@@ -0,0 +1,251 @@
+use crate::{
+    config::FirstWeekday,
+    i18n,
+    logging,
+    ics::{self, Event},
+};
+use chrono::{Datelike, Days, Local, Months, NaiveDate, Weekday};
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Button, Grid, Label, Orientation, accessible::Property};
+use std::{cell::Cell, path::PathBuf, rc::Rc};
+
+/// `[calendar]` settings.
+#[derive(Clone, Debug)]
+pub struct Calendar {
+    /// `[calendar] first_weekday`
+    pub first_weekday: FirstWeekday,
+    /// `[calendar] week_numbers`, ISO week numbers in front of every row
+    pub week_numbers: bool,
+    /// `[calendar] expanded`, start with the whole month instead of the current week
+    pub expanded: bool,
+    /// `[calendar] events`, an iCalendar file whose events are marked
+    pub events: Option<PathBuf>,
+}
+
+impl Default for Calendar {
+    fn default() -> Self {
+        Self {
+            first_weekday: FirstWeekday::Monday,
+            week_numbers: false,
+            expanded: false,
+            events: None,
+        }
+    }
+}
+
+fn weekday(first: FirstWeekday) -> Weekday {
+    match first {
+        FirstWeekday::Monday => Weekday::Mon,
+        FirstWeekday::Tuesday => Weekday::Tue,
+        FirstWeekday::Wednesday => Weekday::Wed,
+        FirstWeekday::Thursday => Weekday::Thu,
+        FirstWeekday::Friday => Weekday::Fri,
+        FirstWeekday::Saturday => Weekday::Sat,
+        FirstWeekday::Sunday => Weekday::Sun,
+    }
+}
+
+/// The rows shown: `date`'s week, or every week touching `date`'s month.
+fn weeks(date: NaiveDate, first: Weekday, month: bool) -> Vec<NaiveDate> {
+    let (from, to) = if month {
+        let from = date.with_day(1).unwrap_or(date);
+        let to = from + Months::new(1) - Days::new(1);
+        (from, to)
+    } else {
+        (date, date)
+    };
+    let mut start = from.week(first).first_day();
+    let mut rows = Vec::new();
+    while start <= to {
+        rows.push(start);
+        start = start + Days::new(7);
+    }
+    rows
+}
+
+fn describe(date: NaiveDate, events: &[Event]) -> String {
+    let title = i18n::localize(&date, &i18n::date_format("day_title"));
+    let mut lines = vec![date.format(&title).to_string()];
+    for event in events.iter().filter(|e| e.occurs_on(date)) {
+        match event.time {
+            Some(time) if event.start == date => lines.push(format!("{} {}", time.format("%H:%M"), event.summary)),
+            _ => lines.push(event.summary.clone()),
+        }
+    }
+    lines.join("\n")
+}
+
+/// Clears `grid` and fills it with the rows around `shown`.
+fn fill(grid: &Grid, shown: NaiveDate, expanded: bool, calendar: &Calendar, events: &Rc<Vec<Event>>, details: &Label) {
+    while let Some(child) = grid.first_child() {
+        grid.remove(&child);
+    }
+
+    let first = weekday(calendar.first_weekday);
+    let column = if calendar.week_numbers { 1 } else { 0 };
+    for i in 0..7 {
+        let day = i18n::weekday_short((first.num_days_from_monday() as usize + i) % 7);
+        let label = Label::new(Some(&day));
+        label.add_css_class("day-label");
+        grid.attach(&label, column + i as i32, 0, 1, 1);
+    }
+
+    let today = Local::now().date_naive();
+    for (row, start) in weeks(shown, first, expanded).into_iter().enumerate() {
+        let row = row as i32 + 1;
+        if calendar.week_numbers {
+            // The row's middle day decides its ISO week, most of the row is in it.
+            let middle = start + Days::new(3);
+            let label = Label::new(Some(&middle.iso_week().week().to_string()));
+            label.add_css_class("week-number");
+            grid.attach(&label, 0, row, 1, 1);
+        }
+
+        for i in 0..7 {
+            let date = start + Days::new(i);
+            let button = Button::with_label(&date.day().to_string());
+            button.add_css_class("date-button");
+            let title = i18n::localize(&date, &i18n::date_format("day_title"));
+            button.update_property(&[Property::Label(&date.format(&title).to_string())]);
+            if date == today {
+                button.add_css_class("today");
+            }
+            if expanded && date.month() != shown.month() {
+                button.add_css_class("other-month");
+            }
+            if events.iter().any(|e| e.occurs_on(date)) {
+                button.add_css_class("has-events");
+                button.set_tooltip_text(Some(&describe(date, events)));
+            }
+
+            let events = events.clone();
+            let details = details.clone();
+            button.connect_clicked(move |_| {
+                details.set_text(&describe(date, &events));
+                details.set_visible(true);
+            });
+            grid.attach(&button, column + i as i32, row, 1, 1);
+        }
+    }
+}
+
+/// The calendar under the clock: the current week, expandable to a browsable month.
+pub fn widget(calendar: &Calendar) -> GtkBox {
+    let events = match &calendar.events {
+        Some(path) => ics::load(path).unwrap_or_else(|e| {
+            logging::error(&e.to_string(), &[]);
+            Vec::new()
+        }),
+        None => Vec::new(),
+    };
+    let events = Rc::new(events);
+
+    let container = GtkBox::new(Orientation::Vertical, 8);
+    container.add_css_class("calendar-container");
+
+    let header = GtkBox::new(Orientation::Horizontal, 8);
+    header.add_css_class("calendar-header");
+    header.set_halign(gtk4::Align::Center);
+    let prev = Button::with_label("‹");
+    let title = Label::new(None);
+    title.add_css_class("month-label");
+    let next = Button::with_label("›");
+    let toggle = Button::new();
+    toggle.add_css_class("calendar-toggle");
+    for button in [&prev, &next, &toggle] {
+        button.add_css_class("calendar-nav");
+    }
+    header.append(&prev);
+    header.append(&title);
+    header.append(&next);
+    header.append(&toggle);
+
+    let grid = Grid::new();
+    grid.set_column_spacing(10);
+    grid.set_row_spacing(10);
+
+    let details = Label::new(None);
+    details.add_css_class("calendar-events");
+    details.set_visible(false);
+
+    container.append(&header);
+    container.append(&grid);
+    container.append(&details);
+
+    let today = Local::now().date_naive();
+    let shown = Rc::new(Cell::new(today));
+    let expanded = Rc::new(Cell::new(calendar.expanded));
+    let refresh = {
+        let calendar = calendar.clone();
+        let shown = shown.clone();
+        let expanded = expanded.clone();
+        let (prev, next, toggle) = (prev.clone(), next.clone(), toggle.clone());
+        move || {
+            let (date, month) = (shown.get(), expanded.get());
+            fill(&grid, date, month, &calendar, &events, &details);
+            let format = i18n::localize(&date, &i18n::date_format("month_title"));
+            title.set_text(&date.format(&format).to_string());
+            prev.set_visible(month);
+            next.set_visible(month);
+            toggle.set_label(if month { "▴" } else { "▾" });
+            let names = [
+                (&prev, "previous_month"),
+                (&next, "next_month"),
+                (&toggle, if month { "show_week" } else { "show_month" }),
+            ];
+            for (button, id) in names {
+                let name = i18n::tr(id);
+                button.set_tooltip_text(Some(&name));
+                button.update_property(&[Property::Label(&name)]);
+            }
+            if !month {
+                details.set_visible(false);
+            }
+        }
+    };
+    let refresh = Rc::new(refresh);
+    {
+        let refresh = refresh.clone();
+        i18n::on_change(move || refresh());
+    }
+    for (button, forward) in [(prev, false), (next, true)] {
+        let shown = shown.clone();
+        let refresh = refresh.clone();
+        button.connect_clicked(move |_| {
+            let first = shown.get().with_day(1).unwrap_or(shown.get());
+            shown.set(if forward { first + Months::new(1) } else { first - Months::new(1) });
+            refresh();
+        });
+    }
+    toggle.connect_clicked(move |_| {
+        expanded.set(!expanded.get());
+        // Folding back always shows the current week.
+        shown.set(Local::now().date_naive());
+        refresh();
+    });
+
+    container.set_hexpand(true);
+    container.set_margin_top(10);
+    container.set_halign(gtk4::Align::Center);
+    container
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn month_rows() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+
+        let rows = weeks(date, Weekday::Mon, true);
+        assert_eq!(rows.first(), NaiveDate::from_ymd_opt(2026, 9, 28).as_ref());
+        assert_eq!(rows.len(), 5);
+
+        let rows = weeks(date, Weekday::Sun, true);
+        assert_eq!(rows.first(), NaiveDate::from_ymd_opt(2026, 9, 27).as_ref());
+        assert_eq!(rows.len(), 5);
+
+        assert_eq!(weeks(date, Weekday::Sun, false), NaiveDate::from_ymd_opt(2026, 10, 18).into_iter().collect::<Vec<_>>());
+    }
+}
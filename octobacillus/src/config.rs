@@ -0,0 +1,874 @@
+use crate::{
+    accessibility::Accessibility,
+    background::Background,
+    banner::Banner,
+    calendar::Calendar,
+    demo::Demo,
+    idle::Idle,
+    keyboard::Keyboard,
+    logging::Log,
+    osk::{self, Osk},
+    power::{Action, Power},
+    sound::Sound,
+    statusbar::StatusBar,
+};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    fs,
+    io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+pub const DEFAULT_CONFIG_PATH: &str = "/etc/octobacillus/config.ini";
+
+type Section<'a> = HashMap<&'a str, &'a str>;
+
+/// A keyword value that is not one of the accepted ones.
+#[derive(Debug)]
+pub struct UnknownValue {
+    value: String,
+    expected: &'static str,
+}
+
+impl fmt::Display for UnknownValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown value `{}`, expected one of: {}", self.value, self.expected)
+    }
+}
+
+impl Error for UnknownValue {}
+
+/// Declares a keyword enum that parses from its lowercase config spelling.
+macro_rules! keyword_enum {
+    ($(#[$meta:meta])* $name:ident { $($variant:ident = $word:literal),+ $(,)? }) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        pub enum $name {
+            $($variant),+
+        }
+
+        impl FromStr for $name {
+            type Err = UnknownValue;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    $($word => Ok($name::$variant),)+
+                    _ => Err(UnknownValue {
+                        value: s.to_string(),
+                        expected: concat!($($word, " "),+).trim_ascii_end(),
+                    }),
+                }
+            }
+        }
+    };
+}
+
+keyword_enum! {
+    /// `[window] layer_shell`: whether to put the greeter on a wlr-layer-shell surface.
+    LayerShell { Auto = "auto", Always = "always", Never = "never" }
+}
+
+keyword_enum! {
+    /// `[window] layer`
+    Layer { Background = "background", Bottom = "bottom", Top = "top", Overlay = "overlay" }
+}
+
+keyword_enum! {
+    /// `[background] mode`: one image (possibly animated), a slideshow of a directory, or a color.
+    BackgroundMode { Image = "image", Slideshow = "slideshow", Color = "color" }
+}
+
+keyword_enum! {
+    /// `[background] scaling`: how pictures are fitted to the screen.
+    Scaling { Stretch = "stretch", Fill = "fill", Fit = "fit", Center = "center", Tile = "tile" }
+}
+
+keyword_enum! {
+    /// `[calendar] first_weekday`
+    FirstWeekday {
+        Monday = "monday",
+        Tuesday = "tuesday",
+        Wednesday = "wednesday",
+        Thursday = "thursday",
+        Friday = "friday",
+        Saturday = "saturday",
+        Sunday = "sunday",
+    }
+}
+
+keyword_enum! {
+    /// `[log] target`: journald if it is running, else stderr, or the one named.
+    LogTarget { Auto = "auto", Journald = "journald", File = "file", Stderr = "stderr" }
+}
+
+keyword_enum! {
+    /// `[window] keyboard`: keyboard interactivity of the login form's layer surface.
+    KeyboardInteractivity { None = "none", Exclusive = "exclusive", OnDemand = "on_demand" }
+}
+
+/// Everything the greeter used to hardcode. See `config.ini` for the documented keys.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// `[background]` and `[paths] background`
+    pub background: Background,
+    /// `[paths] user`, the file holding the remembered `name = ...`
+    pub user_file: PathBuf,
+    /// `[paths] probe`, cynager's info.probe
+    pub probe: PathBuf,
+    /// `[paths] startup_sounds`, directory the probe's `startup` entry is relative to
+    pub startup_sounds: PathBuf,
+    /// `[paths] themes`, where themes named by `[theme] name` live
+    pub themes: PathBuf,
+    /// `[paths] locales`, translations added to or overriding the built-in ones
+    pub locales: PathBuf,
+    /// greetd's socket, `--socket` or `GREETD_SOCK`. Not read from the file.
+    pub socket: Option<PathBuf>,
+
+    /// `[display] primary`, connector name of the output showing the login form
+    pub primary_monitor: Option<String>,
+    /// `[display] mirror`, background and clock on the other outputs
+    pub mirror: bool,
+
+    /// `[window] layer_shell`
+    pub layer_shell: LayerShell,
+    /// `[window] layer`
+    pub layer: Layer,
+    /// `[window] keyboard`
+    pub keyboard_interactivity: KeyboardInteractivity,
+
+    /// `[clock] format`, a chrono strftime string
+    pub clock_format: String,
+    /// `[clock] typing_delay` in milliseconds per character
+    pub typing_delay: u64,
+
+    /// `[calendar]`, the week or month under the clock
+    pub calendar: Calendar,
+
+    /// `[animation] fade_duration` in milliseconds
+    pub fade_duration: Duration,
+    /// `[animation] bounce_amplitude` in pixels
+    pub bounce_amplitude: f64,
+    /// `[animation] bounce_speed` in bounces per second
+    pub bounce_speed: f64,
+
+    /// `[auth] max_attempts`
+    pub max_attempts: u32,
+    /// `[auth] lockout` in seconds, doubled on every lockout in a row
+    pub lockout_base: Duration,
+    /// `[auth] lockout_max` in seconds
+    pub lockout_max: Duration,
+
+    /// `[theme] name`, a theme directory name or a path, the built-in style if unset
+    pub theme: Option<String>,
+    /// `[theme] hot_reload`, reloads the theme whenever its files change
+    pub hot_reload: bool,
+
+    /// `[i18n] language`, the greeter's locale instead of `LC_ALL`/`LC_MESSAGES`/`LANG`
+    pub language: Option<String>,
+    /// `[i18n] languages`, comma separated locales offered by the language picker
+    pub languages: Vec<String>,
+
+    /// `[accessibility]`, styles and animations
+    pub accessibility: Accessibility,
+
+    /// `[sound]`, event sounds
+    pub sound: Sound,
+
+    /// `[osk]`, the on-screen keyboard
+    pub osk: Osk,
+
+    /// `[idle]`, dimming and blanking when nobody is around
+    pub idle: Idle,
+
+    /// `[status_bar]`, battery, network and host
+    pub status_bar: StatusBar,
+
+    /// `[banner]`, notices shown above the login form
+    pub banner: Banner,
+
+    /// `[session] command`
+    pub command: Vec<String>,
+    /// `[environment]`, every key becomes `KEY=value`
+    pub env: Vec<String>,
+    /// `[environment NAME]`, added for sessions whose program is called `NAME`
+    pub session_env: HashMap<String, Vec<String>>,
+
+    /// `[demo]`, the fake greetd used for theming
+    pub demo: Demo,
+
+    /// `[power]`, the power menu
+    pub power: Power,
+
+    /// `[keyboard]`, lock indicators and layouts
+    pub keyboard: Keyboard,
+
+    /// `[log]`, where lifecycle events and errors are recorded
+    pub log: Log,
+
+    /// The problems of keys left at their defaults for having bad values.
+    pub skipped: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            background: Background::default(),
+            user_file: PathBuf::from("/usr/share/octobacillus/user.octo"),
+            probe: PathBuf::from("/var/lib/cynager/info.probe"),
+            startup_sounds: PathBuf::from("/var/lib/cynager/niri/sound/startup/"),
+            themes: PathBuf::from("/usr/share/octobacillus/themes"),
+            locales: PathBuf::from("/usr/share/octobacillus/locales"),
+            socket: None,
+            primary_monitor: None,
+            mirror: true,
+            layer_shell: LayerShell::Auto,
+            layer: Layer::Overlay,
+            keyboard_interactivity: KeyboardInteractivity::Exclusive,
+            clock_format: "%I:%M %p".to_string(),
+            typing_delay: 50,
+            calendar: Calendar::default(),
+            fade_duration: Duration::from_millis(500),
+            bounce_amplitude: 10.0,
+            bounce_speed: 0.7,
+            max_attempts: 3,
+            lockout_base: Duration::from_secs(30),
+            lockout_max: Duration::from_secs(15 * 60),
+            theme: None,
+            hot_reload: false,
+            language: None,
+            languages: Vec::new(),
+            accessibility: Accessibility::default(),
+            sound: Sound::default(),
+            osk: Osk::default(),
+            idle: Idle::default(),
+            status_bar: StatusBar::default(),
+            banner: Banner::default(),
+            command: vec!["niri-session".to_string()],
+            env: vec!["NIRI_CONFIG=/var/lib/cynager/niri/config.kdl".to_string()],
+            session_env: HashMap::new(),
+            demo: Demo::default(),
+            power: Power::default(),
+            keyboard: Keyboard::default(),
+            log: Log::default(),
+            skipped: Vec::new(),
+        }
+    }
+}
+
+fn unquote(value: &str) -> Result<String, Box<dyn Error>> {
+    if value.starts_with(['"', '\'', '`']) {
+        Ok(enquote::unquote(value)?)
+    } else {
+        Ok(value.to_string())
+    }
+}
+
+fn get<T>(config: &HashMap<&str, Section>, section: &str, key: &str) -> Result<Option<T>, Box<dyn Error>>
+where
+    T: FromStr,
+    T::Err: Error + 'static,
+{
+    let Some(value) = config.get(section).and_then(|s| s.get(key)) else {
+        return Ok(None);
+    };
+    unquote(value)?
+        .parse()
+        .map(Some)
+        .map_err(|e| format!("[{section}] {key}: {e}").into())
+}
+
+/// Sections where a bad value stops the greeter instead of being skipped: at their
+/// defaults a lockout could be lifted, the banner dropped or another session started.
+const STRICT: [&str; 3] = ["auth", "banner", "session"];
+
+/// The parsed ini file and what was wrong with it so far.
+struct Reader<'a> {
+    ini: HashMap<&'a str, Section<'a>>,
+    skipped: Vec<String>,
+    fatal: Option<String>,
+}
+
+impl Reader<'_> {
+    /// Like `get`, but a bad value is recorded and reads as unset.
+    fn get<T>(&mut self, section: &str, key: &str) -> Option<T>
+    where
+        T: FromStr,
+        T::Err: Error + 'static,
+    {
+        get(&self.ini, section, key).unwrap_or_else(|e| {
+            self.problem(section, e.to_string());
+            None
+        })
+    }
+
+    fn problem(&mut self, section: &str, problem: String) {
+        if STRICT.contains(&section) {
+            self.fatal.get_or_insert(problem);
+        } else {
+            self.skipped.push(problem);
+        }
+    }
+}
+
+/// What was picked on the greeter, for the placeholders of the session environment.
+#[derive(Clone, Debug, Default)]
+pub struct Choices {
+    /// `{locale}`, e.g. `de_DE.UTF-8`
+    pub locale: Option<String>,
+    /// `{layout}`, the XKB layout
+    pub layout: Option<String>,
+}
+
+impl Choices {
+    fn expand(&self, pair: &str) -> Option<String> {
+        let mut pair = pair.to_string();
+        for (placeholder, value) in [("{locale}", &self.locale), ("{layout}", &self.layout)] {
+            if pair.contains(placeholder) {
+                pair = pair.replace(placeholder, value.as_deref()?);
+            }
+        }
+        Some(pair)
+    }
+}
+
+fn replace_env(env: &mut Vec<String>, pair: &str) {
+    let key = pair.split_once('=').map_or(pair, |(key, _)| key);
+    env.retain(|e| e.split_once('=').map_or(e.as_str(), |(key, _)| key) != key);
+    env.push(pair.to_string());
+}
+
+/// A comma separated list, empty items dropped.
+fn list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+impl Config {
+    /// Parses a config file. A key with a bad value is left at its default and
+    /// listed in `skipped`, except in the `STRICT` sections where it is an error.
+    pub fn parse(s: &str) -> Result<Self, Box<dyn Error>> {
+        let mut r = Reader {
+            ini: inish::parse(s)?,
+            skipped: Vec::new(),
+            fatal: None,
+        };
+        let mut config = Config::default();
+
+        if let Some(v) = r.get("paths", "background") {
+            config.background.image = v;
+        }
+        if let Some(v) = r.get("paths", "user") {
+            config.user_file = v;
+        }
+        if let Some(v) = r.get("paths", "probe") {
+            config.probe = v;
+        }
+        if let Some(v) = r.get("paths", "startup_sounds") {
+            config.startup_sounds = v;
+        }
+        if let Some(v) = r.get("paths", "themes") {
+            config.themes = v;
+        }
+        if let Some(v) = r.get("paths", "locales") {
+            config.locales = v;
+        }
+
+        if let Some(v) = r.get("background", "mode") {
+            config.background.mode = v;
+        }
+        if let Some(v) = r.get("background", "scaling") {
+            config.background.scaling = v;
+        }
+        if let Some(v) = r.get("background", "directory") {
+            config.background.directory = v;
+        }
+        if let Some(v) = r.get("background", "interval") {
+            config.background.interval = Duration::from_secs(v);
+        }
+        if let Some(v) = r.get("background", "crossfade") {
+            config.background.crossfade = Duration::from_millis(v);
+        }
+        if let Some(v) = r.get("background", "color") {
+            config.background.color = v;
+        }
+        config.background.gradient = r.get("background", "gradient");
+        if let Some(v) = r.get("background", "angle") {
+            config.background.angle = v;
+        }
+
+        config.primary_monitor = r.get("display", "primary");
+        if let Some(v) = r.get("display", "mirror") {
+            config.mirror = v;
+        }
+
+        if let Some(v) = r.get("window", "layer_shell") {
+            config.layer_shell = v;
+        }
+        if let Some(v) = r.get("window", "layer") {
+            config.layer = v;
+        }
+        if let Some(v) = r.get("window", "keyboard") {
+            config.keyboard_interactivity = v;
+        }
+
+        if let Some(v) = r.get("clock", "format") {
+            config.clock_format = v;
+        }
+        if let Some(v) = r.get("clock", "typing_delay") {
+            config.typing_delay = v;
+        }
+
+        if let Some(v) = r.get("calendar", "first_weekday") {
+            config.calendar.first_weekday = v;
+        }
+        if let Some(v) = r.get("calendar", "week_numbers") {
+            config.calendar.week_numbers = v;
+        }
+        if let Some(v) = r.get("calendar", "expanded") {
+            config.calendar.expanded = v;
+        }
+        config.calendar.events = r.get("calendar", "events");
+
+        if let Some(v) = r.get("animation", "fade_duration") {
+            config.fade_duration = Duration::from_millis(v);
+        }
+        if let Some(v) = r.get("animation", "bounce_amplitude") {
+            config.bounce_amplitude = v;
+        }
+        if let Some(v) = r.get("animation", "bounce_speed") {
+            config.bounce_speed = v;
+        }
+
+        if let Some(v) = r.get("auth", "max_attempts") {
+            config.max_attempts = v;
+        }
+        if let Some(v) = r.get("auth", "lockout") {
+            config.lockout_base = Duration::from_secs(v);
+        }
+        if let Some(v) = r.get("auth", "lockout_max") {
+            config.lockout_max = Duration::from_secs(v);
+        }
+
+        config.theme = r.get("theme", "name");
+        if let Some(v) = r.get("theme", "hot_reload") {
+            config.hot_reload = v;
+        }
+
+        config.language = r.get("i18n", "language");
+        if let Some(v) = r.get::<String>("i18n", "languages") {
+            config.languages = list(&v);
+        }
+
+        if let Some(v) = r.get("accessibility", "animations") {
+            config.accessibility.animations = v;
+        }
+        if let Some(v) = r.get("accessibility", "high_contrast") {
+            config.accessibility.high_contrast = v;
+        }
+        if let Some(v) = r.get("accessibility", "large_text") {
+            config.accessibility.large_text = v;
+        }
+        if let Some(v) = r.get("accessibility", "toggles") {
+            config.accessibility.toggles = v;
+        }
+
+        if let Some(v) = r.get("sound", "enabled") {
+            config.sound.enabled = v;
+        }
+        if let Some(v) = r.get("sound", "volume") {
+            config.sound.volume = v;
+        }
+        if let Some(v) = r.get("sound", "mute_button") {
+            config.sound.mute_button = v;
+        }
+        let sound = &mut config.sound;
+        for (key, cue) in [
+            ("startup", &mut sound.startup),
+            ("login", &mut sound.login),
+            ("failure", &mut sound.failure),
+            ("lockout", &mut sound.lockout),
+        ] {
+            if let Some(v) = r.get("sound", key) {
+                cue.file = Some(v);
+            }
+            if let Some(v) = r.get("sound", &format!("{key}_volume")) {
+                cue.volume = v;
+            }
+        }
+
+        if let Some(v) = r.get("osk", "enabled") {
+            config.osk.enabled = v;
+        }
+        if let Some(v) = r.get("osk", "auto_show") {
+            config.osk.auto_show = v;
+        }
+        if let Some(v) = r.get::<String>("osk", "layouts") {
+            // `[osk_layouts]` defines layouts, or overrides the built-in ones.
+            let layouts = list(&v)
+                .into_iter()
+                .map(|name| match get::<String>(&r.ini, "osk_layouts", &name)? {
+                    Some(rows) => Ok(osk::layout(&name, &rows)),
+                    None => osk::builtin(&name).ok_or_else(|| format!("[osk] layouts: unknown layout `{name}`").into()),
+                })
+                .collect::<Result<Vec<_>, Box<dyn Error>>>();
+            match layouts {
+                Ok(layouts) if layouts.iter().any(|layout| layout.rows.is_empty()) => {
+                    r.problem("osk", "[osk] layouts: a layout has no keys".to_string());
+                }
+                Ok(layouts) => config.osk.layouts = layouts,
+                Err(e) => r.problem("osk", e.to_string()),
+            }
+        }
+
+        if let Some(v) = r.get("idle", "dim_after") {
+            config.idle.dim_after = Duration::from_secs(v);
+        }
+        if let Some(v) = r.get("idle", "blank_after") {
+            config.idle.blank_after = Duration::from_secs(v);
+        }
+        if let Some(v) = r.get("idle", "dim_brightness") {
+            config.idle.dim_brightness = v;
+        }
+        if let Some(v) = r.get("idle", "dpms_off") {
+            config.idle.dpms_off = Some(v);
+        }
+        if let Some(v) = r.get("idle", "dpms_on") {
+            config.idle.dpms_on = Some(v);
+        }
+
+        let status_bar = &mut config.status_bar;
+        for (key, shown) in [
+            ("enabled", &mut status_bar.enabled),
+            ("battery", &mut status_bar.battery),
+            ("network", &mut status_bar.network),
+            ("hostname", &mut status_bar.hostname),
+            ("kernel", &mut status_bar.kernel),
+            ("uptime", &mut status_bar.uptime),
+        ] {
+            if let Some(v) = r.get("status_bar", key) {
+                *shown = v;
+            }
+        }
+        if let Some(v) = r.get("status_bar", "interval") {
+            config.status_bar.interval = Duration::from_secs(v);
+        }
+
+        config.banner.issue = r.get("banner", "issue");
+        config.banner.motd = r.get("banner", "motd");
+        if let Some(v) = r.get("banner", "must_acknowledge") {
+            config.banner.must_acknowledge = v;
+        }
+
+        if let Some(v) = r.get::<String>("session", "command") {
+            let command: Vec<String> = v.split_whitespace().map(str::to_string).collect();
+            if command.is_empty() {
+                r.problem("session", "[session] command: must not be empty".to_string());
+            } else {
+                config.command = command;
+            }
+        }
+        let mut problems = Vec::new();
+        for (name, section) in &r.ini {
+            let mut env: Vec<String> = section
+                .iter()
+                .filter_map(|(key, value)| match unquote(value) {
+                    Ok(value) => Some(format!("{key}={value}")),
+                    Err(e) => {
+                        problems.push(format!("[{name}] {key}: {e}"));
+                        None
+                    }
+                })
+                .collect();
+            env.sort();
+            if *name == "environment" {
+                config.env = env;
+            } else if let Some(session) = name.strip_prefix("environment ") {
+                config.session_env.insert(session.trim().to_string(), env);
+            }
+        }
+
+        for problem in problems {
+            r.problem("environment", problem);
+        }
+
+        if let Some(v) = r.get("demo", "enabled") {
+            config.demo.enabled = v;
+        }
+        if let Some(v) = r.get("demo", "password") {
+            config.demo.password = v;
+        }
+        config.demo.otp = r.get("demo", "otp");
+        config.demo.info = r.get("demo", "info");
+        if let Some(v) = r.get("demo", "delay") {
+            config.demo.delay = Duration::from_millis(v);
+        }
+
+        if let Some(v) = r.get("power", "enabled") {
+            config.power.enabled = v;
+        }
+        if let Some(v) = r.get("power", "confirm") {
+            config.power.confirm = v;
+        }
+        for (action, key) in Action::ALL.into_iter().zip(["poweroff", "reboot", "suspend", "hibernate"]) {
+            if let Some(v) = r.get::<String>("power", key) {
+                let cmd: Vec<String> = v.split_whitespace().map(str::to_string).collect();
+                if cmd.is_empty() {
+                    r.problem("power", format!("[power] {key}: must not be empty"));
+                } else {
+                    config.power.commands.push((action, cmd));
+                }
+            }
+        }
+
+        if let Some(v) = r.get("keyboard", "lock_indicator") {
+            config.keyboard.lock_indicator = v;
+        }
+        if let Some(v) = r.get::<String>("keyboard", "layouts") {
+            config.keyboard.layouts = list(&v);
+        }
+        config.keyboard.switch_command = r.get("keyboard", "switch_command");
+
+        if let Some(v) = r.get("log", "target") {
+            config.log.target = v;
+        }
+        if let Some(v) = r.get("log", "file") {
+            config.log.file = v;
+        }
+        if let Some(v) = r.get("log", "max_size") {
+            config.log.max_size = v;
+        }
+        if let Some(v) = r.get("log", "keep") {
+            config.log.keep = v;
+        }
+        if let Some(v) = r.get("log", "debug") {
+            config.log.debug = v;
+        }
+
+        if let Some(fatal) = r.fatal {
+            return Err(fatal.into());
+        }
+        config.skipped = r.skipped;
+        Ok(config)
+    }
+
+    /// Adds a `KEY=value` pair to the session environment, replacing an earlier value for `KEY`.
+    pub fn set_env(&mut self, pair: &str) -> Result<(), Box<dyn Error>> {
+        if !pair.contains('=') {
+            return Err(format!("{pair}: expected KEY=VALUE").into());
+        }
+        replace_env(&mut self.env, pair);
+        Ok(())
+    }
+
+    /// The environment of a session started with `cmd`: `[environment]`, with the
+    /// session's `[environment NAME]` on top and `{locale}` and `{layout}` filled in
+    /// from `choices`. A variable whose placeholder has no value is left out.
+    pub fn session_env(&self, cmd: &[String], choices: &Choices) -> Vec<String> {
+        let mut env = self.env.clone();
+        let program = cmd.first().and_then(|program| Path::new(program).file_name()?.to_str());
+        if let Some(extra) = program.and_then(|program| self.session_env.get(program)) {
+            for pair in extra {
+                replace_env(&mut env, pair);
+            }
+        }
+        env.iter().filter_map(|pair| choices.expand(pair)).collect()
+    }
+
+    /// Loads the config file, falling back to the defaults if it does not exist.
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        match fs::read_to_string(path) {
+            Ok(s) => Self::parse(&s).map_err(|e| format!("{path}: {e}").into()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("{path}: {e}").into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_is_default() {
+        let config = Config::parse("").expect("config didn't parse");
+        assert_eq!(config.clock_format, "%I:%M %p");
+        assert_eq!(config.fade_duration, Duration::from_millis(500));
+        assert_eq!(config.command, ["niri-session"]);
+        assert_eq!(config.env, ["NIRI_CONFIG=/var/lib/cynager/niri/config.kdl"]);
+    }
+
+    #[test]
+    fn sample_is_default() {
+        let config = Config::parse(include_str!("../config.ini")).expect("config.ini didn't parse");
+        let default = Config::default();
+        assert_eq!(config.background.image, default.background.image);
+        assert_eq!(config.clock_format, default.clock_format);
+        assert_eq!(config.lockout_max, default.lockout_max);
+        assert_eq!(config.command, default.command);
+        assert_eq!(config.env, default.env);
+    }
+
+    #[test]
+    fn overrides() {
+        let config = Config::parse(
+            "
+[paths]
+background = /tmp/bg.png
+
+[clock]
+format = '%H:%M'
+
+[animation]
+fade_duration = 250
+bounce_amplitude = 0
+
+[session]
+command = sway --unsupported-gpu
+
+[environment]
+XDG_CURRENT_DESKTOP = sway
+LANG = \"de_DE.UTF-8\"
+",
+        )
+        .expect("config didn't parse");
+
+        assert_eq!(config.background.image, PathBuf::from("/tmp/bg.png"));
+        assert_eq!(config.clock_format, "%H:%M");
+        assert_eq!(config.fade_duration, Duration::from_millis(250));
+        assert_eq!(config.bounce_amplitude, 0.0);
+        assert_eq!(config.command, ["sway", "--unsupported-gpu"]);
+        assert_eq!(config.env, ["LANG=de_DE.UTF-8", "XDG_CURRENT_DESKTOP=sway"]);
+    }
+
+    #[test]
+    fn background() {
+        let config = Config::parse(
+            "[background]\nmode = slideshow\nscaling = fill\ninterval = 60\ngradient = #224",
+        )
+        .expect("config didn't parse");
+        assert_eq!(config.background.mode, BackgroundMode::Slideshow);
+        assert_eq!(config.background.scaling, Scaling::Fill);
+        assert_eq!(config.background.interval, Duration::from_secs(60));
+        assert_eq!(config.background.gradient.as_deref(), Some("#224"));
+        assert_eq!(config.background.color, "#000000");
+    }
+
+    #[test]
+    fn sounds() {
+        let config = Config::parse("[sound]\nvolume = 0.5\nfailure = /tmp/no.ogg\nfailure_volume = 0.8")
+            .expect("config didn't parse");
+        assert_eq!(config.sound.volume, 0.5);
+        assert_eq!(config.sound.failure.file, Some(PathBuf::from("/tmp/no.ogg")));
+        assert_eq!(config.sound.failure.volume, 0.8);
+        assert_eq!(config.sound.login.file, None);
+    }
+
+    #[test]
+    fn osk_layouts() {
+        let config = Config::parse("[osk]\nenabled = true\nlayouts = pin, azerty\n[osk_layouts]\npin = 123 456 789 0")
+            .expect("config didn't parse");
+        assert!(config.osk.enabled);
+        let names: Vec<&str> = config.osk.layouts.iter().map(|layout| layout.name.as_str()).collect();
+        assert_eq!(names, ["pin", "azerty"]);
+        assert_eq!(config.osk.layouts[0].rows, [vec!['1', '2', '3'], vec!['4', '5', '6'], vec!['7', '8', '9'], vec!['0']]);
+
+        let config = Config::parse("[osk]\nlayouts = dvorak").expect("config didn't parse");
+        assert_eq!(config.skipped, ["[osk] layouts: unknown layout `dvorak`"]);
+        assert_eq!(config.osk.layouts.len(), Osk::default().layouts.len());
+    }
+
+    #[test]
+    fn layouts() {
+        let config = Config::parse("[keyboard]\nlayouts = us, de ,, fr").expect("config didn't parse");
+        assert_eq!(config.keyboard.layouts, ["us", "de", "fr"]);
+
+        let config = Config::parse("[i18n]\nlanguages = en_US.UTF-8, de_DE.UTF-8").expect("config didn't parse");
+        assert_eq!(config.languages, ["en_US.UTF-8", "de_DE.UTF-8"]);
+    }
+
+    #[test]
+    fn session_environment() {
+        let config = Config::parse(
+            "
+[environment]
+LANG = {locale}
+XKB_DEFAULT_LAYOUT = {layout}
+XDG_SESSION_TYPE = wayland
+
+[environment sway]
+XDG_CURRENT_DESKTOP = sway
+XDG_SESSION_TYPE = wayland-sway
+",
+        )
+        .expect("config didn't parse");
+
+        let choices = Choices {
+            locale: Some("de_DE.UTF-8".to_string()),
+            layout: None,
+        };
+        let sway = ["/usr/bin/sway".to_string(), "--unsupported-gpu".to_string()];
+        assert_eq!(
+            config.session_env(&sway, &choices),
+            ["LANG=de_DE.UTF-8", "XDG_CURRENT_DESKTOP=sway", "XDG_SESSION_TYPE=wayland-sway"]
+        );
+        assert_eq!(
+            config.session_env(&["niri-session".to_string()], &choices),
+            ["LANG=de_DE.UTF-8", "XDG_SESSION_TYPE=wayland"]
+        );
+    }
+
+    #[test]
+    fn set_env_replaces() {
+        let mut config = Config::default();
+        config.set_env("NIRI_CONFIG=/tmp/niri.kdl").expect("valid pair");
+        config.set_env("LANG=C").expect("valid pair");
+        assert_eq!(config.env, ["NIRI_CONFIG=/tmp/niri.kdl", "LANG=C"]);
+        assert!(config.set_env("LANG").is_err());
+    }
+
+    #[test]
+    fn keywords() {
+        let config = Config::parse("[window]\nlayer_shell = never\nkeyboard = on_demand").expect("config didn't parse");
+        assert_eq!(config.layer_shell, LayerShell::Never);
+        assert_eq!(config.layer, Layer::Overlay);
+        assert_eq!(config.keyboard_interactivity, KeyboardInteractivity::OnDemand);
+
+        let config = Config::parse("[calendar]\nfirst_weekday = sunday").expect("config didn't parse");
+        assert_eq!(config.calendar.first_weekday, FirstWeekday::Sunday);
+
+        let config = Config::parse("[log]\ntarget = file").expect("config didn't parse");
+        assert_eq!(config.log.target, LogTarget::File);
+
+        let config = Config::parse("[window]\nlayer = middle").expect("config didn't parse");
+        assert_eq!(
+            config.skipped,
+            ["[window] layer: unknown value `middle`, expected one of: background bottom top overlay"]
+        );
+    }
+
+    #[test]
+    fn bad_value() {
+        let err = Config::parse("[auth]\nmax_attempts = three").unwrap_err();
+        assert!(err.to_string().starts_with("[auth] max_attempts:"));
+        let err = Config::parse("[banner]\nmust_acknowledge = yes").unwrap_err();
+        assert!(err.to_string().starts_with("[banner] must_acknowledge:"));
+
+        // Elsewhere only the bad key is left out.
+        let config =
+            Config::parse("[clock]\ntyping_delay = fast\n[auth]\nmax_attempts = 5\n[banner]\nmust_acknowledge = true")
+                .expect("config didn't parse");
+        assert_eq!(config.typing_delay, 50);
+        assert_eq!(config.max_attempts, 5);
+        assert!(config.banner.must_acknowledge);
+        assert_eq!(config.skipped.len(), 1);
+        assert!(config.skipped[0].starts_with("[clock] typing_delay:"));
+    }
+}
@@ -0,0 +1,149 @@
+use greetd_ipc::{AuthMessageType, ErrorType, Request, Response, codec::SyncCodec};
+use std::{io, os::unix::net::UnixStream, thread, time::Duration};
+
+/// Settings of the fake greetd used by `--demo`.
+#[derive(Clone, Debug)]
+pub struct Demo {
+    /// `[demo] enabled`, or `--demo`
+    pub enabled: bool,
+    /// `[demo] password` the fake PAM stack accepts
+    pub password: String,
+    /// `[demo] otp`, asks for a second secret when set
+    pub otp: Option<String>,
+    /// `[demo] info`, shown as a PAM info message before the password prompt
+    pub info: Option<String>,
+    /// `[demo] delay` in milliseconds, how long checking each answer takes
+    pub delay: Duration,
+}
+
+impl Default for Demo {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            password: "demo".to_string(),
+            otp: None,
+            info: None,
+            delay: Duration::from_millis(800),
+        }
+    }
+}
+
+enum Step {
+    Info(String),
+    Secret(&'static str, String),
+}
+
+impl Demo {
+    /// Starts a fake greetd on one end of a socket pair and returns the other end.
+    pub fn connect(&self) -> io::Result<UnixStream> {
+        let (client, server) = UnixStream::pair()?;
+        let demo = self.clone();
+        thread::spawn(move || demo.serve(server));
+        Ok(client)
+    }
+
+    fn steps(&self) -> Vec<Step> {
+        let mut steps = Vec::new();
+        if let Some(info) = &self.info {
+            steps.push(Step::Info(info.clone()));
+        }
+        steps.push(Step::Secret("Password:", self.password.clone()));
+        if let Some(otp) = &self.otp {
+            steps.push(Step::Secret("Verification code:", otp.clone()));
+        }
+        steps
+    }
+
+    fn prompt(step: &Step) -> Response {
+        match step {
+            Step::Info(text) => Response::AuthMessage {
+                auth_message_type: AuthMessageType::Info,
+                auth_message: text.clone(),
+            },
+            Step::Secret(prompt, _) => Response::AuthMessage {
+                auth_message_type: AuthMessageType::Secret,
+                auth_message: prompt.to_string(),
+            },
+        }
+    }
+
+    fn serve(self, mut stream: UnixStream) {
+        let steps = self.steps();
+        let mut current = 0;
+        let mut accepted = true;
+
+        while let Ok(request) = Request::read_from(&mut stream) {
+            let response = match request {
+                Request::CreateSession { .. } => {
+                    current = 0;
+                    accepted = true;
+                    Self::prompt(&steps[0])
+                }
+                Request::PostAuthMessageResponse { response } => {
+                    if let Some(Step::Secret(_, expected)) = steps.get(current) {
+                        thread::sleep(self.delay);
+                        accepted &= response.as_deref() == Some(expected.as_str());
+                    }
+                    current += 1;
+                    match steps.get(current) {
+                        Some(step) => Self::prompt(step),
+                        None if accepted => Response::Success,
+                        None => Response::Error {
+                            error_type: ErrorType::AuthError,
+                            description: "pam_authenticate: AUTH_ERR".to_string(),
+                        },
+                    }
+                }
+                Request::StartSession { .. } | Request::CancelSession => Response::Success,
+            };
+            if response.write_to(&mut stream).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        auth::{self, UiEvent},
+        ipc,
+    };
+    use std::{
+        sync::{Mutex, mpsc},
+        time::Instant,
+    };
+
+    fn login(demo: &Demo, password: &str) -> Vec<UiEvent> {
+        let flow = Mutex::new(auth::test_flow(3));
+        let first = flow
+            .lock()
+            .unwrap()
+            .login(auth::test_creds(password), Instant::now())
+            .expect("login refused");
+
+        let (tx, rx) = mpsc::channel();
+        let mut stream = demo.connect().expect("socket pair");
+        ipc::run(&mut stream, first, &flow, &tx);
+        drop(tx);
+        rx.iter().collect()
+    }
+
+    #[test]
+    fn accepts_password() {
+        let demo = Demo {
+            info: Some("hello".to_string()),
+            delay: Duration::ZERO,
+            ..Demo::default()
+        };
+        let events = login(&demo, "demo");
+        assert!(matches!(&events[..], [UiEvent::Message(m), UiEvent::Started] if m == "hello"));
+
+        let events = login(&demo, "nope");
+        assert!(matches!(
+            &events[..],
+            [UiEvent::Message(_), UiEvent::WrongPassword { attempts_left: 2 }]
+        ));
+    }
+}
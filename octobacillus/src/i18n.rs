@@ -0,0 +1,261 @@
+use crate::logging;
+use chrono::Datelike;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    env,
+    error::Error,
+    fmt,
+    fs,
+    path::Path,
+    sync::{LazyLock, RwLock},
+};
+
+/// The catalogs shipped with the greeter. English is the fallback for every
+/// message a catalog lacks, so it has to have them all.
+const BUILTIN: [(&str, &str); 3] = [
+    ("en", include_str!("../locales/en.ini")),
+    ("de", include_str!("../locales/de.ini")),
+    ("fr", include_str!("../locales/fr.ini")),
+];
+
+/// The messages and date names of one language, read from an ini file with
+/// `[language]`, `[messages]` and `[date]` sections.
+#[derive(Clone, Debug, Default)]
+pub struct Catalog {
+    sections: HashMap<String, HashMap<String, String>>,
+}
+
+impl Catalog {
+    pub fn parse(s: &str) -> Result<Self, Box<dyn Error>> {
+        let ini = inish::parse(s)?;
+        let mut sections = HashMap::new();
+        for (name, section) in ini {
+            let mut entries = HashMap::new();
+            for (key, value) in section {
+                let value = if value.starts_with(['"', '\'', '`']) {
+                    enquote::unquote(value).map_err(|e| format!("[{name}] {key}: {e}"))?
+                } else {
+                    value.to_string()
+                };
+                entries.insert(key.to_string(), value);
+            }
+            sections.insert(name.to_string(), entries);
+        }
+        Ok(Self { sections })
+    }
+
+    fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.sections.get(section)?.get(key).map(String::as_str)
+    }
+
+    /// The comma separated names of `[date] key`, `count` of them or none.
+    fn names(&self, key: &str, count: usize) -> Option<Vec<&str>> {
+        let names: Vec<&str> = self.get("date", key)?.split(',').map(str::trim).collect();
+        (names.len() == count).then_some(names)
+    }
+
+    fn message(&self, id: &str) -> String {
+        self.get("messages", id)
+            .or_else(|| english().get("messages", id))
+            .unwrap_or(id)
+            .to_string()
+    }
+
+    fn date_name(&self, key: &str, count: usize, index: usize) -> String {
+        self.names(key, count)
+            .or_else(|| english().names(key, count))
+            .map_or_else(String::new, |names| names[index].to_string())
+    }
+
+    /// Puts the names in for `%A`, `%a`, `%B`, `%b` and `%h`.
+    fn localize(&self, date: &impl Datelike, fmt: &str) -> String {
+        let weekday = date.weekday().num_days_from_monday() as usize;
+        let month = date.month0() as usize;
+
+        let mut localized = String::new();
+        let mut chars = fmt.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                localized.push(c);
+                continue;
+            }
+            let name = match chars.next() {
+                Some('A') => self.date_name("weekdays", 7, weekday),
+                Some('a') => self.date_name("weekdays_abbr", 7, weekday),
+                Some('B') => self.date_name("months", 12, month),
+                Some('b' | 'h') => self.date_name("months_short", 12, month),
+                Some(other) => {
+                    localized.push('%');
+                    localized.push(other);
+                    continue;
+                }
+                None => {
+                    localized.push('%');
+                    continue;
+                }
+            };
+            // The name goes through strftime too, a `%` in it is not a directive.
+            localized.push_str(&name.replace('%', "%%"));
+        }
+        localized
+    }
+}
+
+fn english() -> &'static Catalog {
+    static ENGLISH: LazyLock<Catalog> =
+        LazyLock::new(|| Catalog::parse(BUILTIN[0].1).expect("the English catalog parses"));
+    &ENGLISH
+}
+
+static CURRENT: LazyLock<RwLock<Catalog>> = LazyLock::new(|| RwLock::new(english().clone()));
+
+thread_local! {
+    static HOOKS: RefCell<Vec<Box<dyn Fn()>>> = RefCell::new(Vec::new());
+}
+
+/// `de_DE.UTF-8@euro` → `["de_DE", "de"]`, the catalog names to try in order.
+fn candidates(locale: &str) -> Vec<&str> {
+    let locale = locale.split(['.', '@']).next().unwrap_or(locale);
+    let mut names = vec![locale];
+    if let Some((language, _)) = locale.split_once('_') {
+        names.push(language);
+    }
+    names
+}
+
+/// The locale asked for by the environment, `LC_ALL` before `LC_MESSAGES` before `LANG`.
+pub fn env_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.is_empty())
+}
+
+/// Finds the catalog for `locale`, in `dir` first and then among the built-in ones.
+pub fn find(locale: &str, dir: &Path) -> Option<Catalog> {
+    for name in candidates(locale) {
+        let path = dir.join(format!("{name}.ini"));
+        if let Ok(s) = fs::read_to_string(&path) {
+            match Catalog::parse(&s) {
+                Ok(catalog) => return Some(catalog),
+                Err(e) => logging::error(&format!("{}: {e}", path.display()), &[]),
+            }
+        }
+        if let Some((_, s)) = BUILTIN.iter().find(|(builtin, _)| *builtin == name) {
+            return Catalog::parse(s).ok();
+        }
+    }
+    None
+}
+
+/// Switches the language of everything translated from now on, and re-translates
+/// whatever registered with `on_change`. An unknown locale falls back to English.
+pub fn select(locale: &str, dir: &Path) {
+    let catalog = find(locale, dir).unwrap_or_else(|| english().clone());
+    *CURRENT.write().unwrap() = catalog;
+    HOOKS.with(|hooks| hooks.borrow().iter().for_each(|hook| hook()));
+}
+
+/// Runs `hook` now and after every language switch, to (re)set translated text.
+pub fn on_change(hook: impl Fn() + 'static) {
+    hook();
+    HOOKS.with(|hooks| hooks.borrow_mut().push(Box::new(hook)));
+}
+
+/// The catalog's name for itself, e.g. `Deutsch`.
+pub fn language_name(locale: &str, dir: &Path) -> String {
+    find(locale, dir)
+        .and_then(|catalog| catalog.get("language", "name").map(str::to_string))
+        .unwrap_or_else(|| locale.to_string())
+}
+
+/// The message `id` in the current language.
+pub fn tr(id: &str) -> String {
+    CURRENT.read().unwrap().message(id)
+}
+
+/// The message `id` with every `{name}` replaced by its value.
+pub fn trf(id: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    fill(tr(id), args)
+}
+
+/// A `[date]` format of the current language, like `month_title`.
+pub fn date_format(key: &str) -> String {
+    let current = CURRENT.read().unwrap();
+    current
+        .get("date", key)
+        .or_else(|| english().get("date", key))
+        .unwrap_or("%c")
+        .to_string()
+}
+
+/// Two-letter weekday name for the calendar, 0 being Monday.
+pub fn weekday_short(index: usize) -> String {
+    CURRENT.read().unwrap().date_name("weekdays_short", 7, index)
+}
+
+/// Rewrites a strftime format for `date` so it has the current language's
+/// weekday and month names; chrono only knows English ones.
+pub fn localize(date: &impl Datelike, fmt: &str) -> String {
+    CURRENT.read().unwrap().localize(date, fmt)
+}
+
+fn fill(text: String, args: &[(&str, &dyn fmt::Display)]) -> String {
+    args.iter()
+        .fold(text, |text, (name, value)| text.replace(&format!("{{{name}}}"), &value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn keys(catalog: &Catalog, section: &str) -> Vec<String> {
+        let mut keys: Vec<String> = catalog.sections[section].keys().cloned().collect();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn builtin_catalogs_are_complete() {
+        for (name, s) in BUILTIN {
+            let catalog = Catalog::parse(s).unwrap_or_else(|e| panic!("{name}: {e}"));
+            for section in ["language", "messages", "date"] {
+                assert_eq!(keys(&catalog, section), keys(english(), section), "{name} [{section}]");
+            }
+            for (key, count) in [
+                ("weekdays", 7),
+                ("weekdays_abbr", 7),
+                ("weekdays_short", 7),
+                ("months", 12),
+                ("months_short", 12),
+            ] {
+                assert!(catalog.names(key, count).is_some(), "{name} [date] {key}");
+            }
+        }
+    }
+
+    #[test]
+    fn candidates_from_locale() {
+        assert_eq!(candidates("de_DE.UTF-8@euro"), ["de_DE", "de"]);
+        assert_eq!(candidates("C"), ["C"]);
+    }
+
+    #[test]
+    fn translate_and_localize() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let dir = Path::new("/nonexistent");
+
+        let german = find("de_DE.UTF-8", dir).expect("German is built in");
+        assert_eq!(fill(german.message("welcome"), &[("user", &"ekah")]), "willkommen, ekah");
+        let fmt = german.localize(&date, "%A, %e. %B %%");
+        assert_eq!(date.format(&fmt).to_string(), "Montag, 19. Oktober %");
+        assert_eq!(german.date_name("weekdays_short", 7, 6), "So");
+
+        assert!(find("xx_XX", dir).is_none());
+        let english = english();
+        assert_eq!(english.message("no such message"), "no such message");
+        assert_eq!(date.format(&english.localize(&date, "%a %b %Y")).to_string(), "Mon Oct 2026");
+    }
+}
@@ -0,0 +1,177 @@
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use std::{error::Error, fs, path::Path};
+
+/// One `VEVENT` of an iCalendar file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Event {
+    pub summary: String,
+    pub start: NaiveDate,
+    /// Last day of the event, inclusive.
+    pub end: NaiveDate,
+    /// Local start time, `None` for all-day events.
+    pub time: Option<NaiveTime>,
+}
+
+impl Event {
+    pub fn occurs_on(&self, date: NaiveDate) -> bool {
+        self.start <= date && date <= self.end
+    }
+}
+
+/// A date with the time of day, if any.
+type Stamp = (NaiveDate, Option<NaiveTime>);
+
+/// An event whose `END:VEVENT` has not been seen yet.
+#[derive(Default)]
+struct Draft {
+    summary: String,
+    start: Option<Stamp>,
+    end: Option<Stamp>,
+}
+
+/// Joins folded lines: a line starting with a space or tab continues the previous one.
+/// Every joined line keeps the 1-based number of its first line.
+fn unfold(s: &str) -> Vec<(usize, String)> {
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    for (n, line) in s.lines().enumerate() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some((_, last))) => last.push_str(rest),
+            _ => lines.push((n + 1, line.to_string())),
+        }
+    }
+    lines
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push('\n'),
+            Some(c) => out.push(c),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// A `DTSTART`/`DTEND` value: a date, a floating local time or a UTC time (`Z` suffix).
+fn parse_time(value: &str) -> Result<Stamp, Box<dyn Error>> {
+    if value.len() == 8 {
+        return Ok((NaiveDate::parse_from_str(value, "%Y%m%d")?, None));
+    }
+    let (value, utc) = match value.strip_suffix('Z') {
+        Some(value) => (value, true),
+        None => (value, false),
+    };
+    let mut time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")?;
+    if utc {
+        time = Utc.from_utc_datetime(&time).with_timezone(&Local).naive_local();
+    }
+    Ok((time.date(), Some(time.time())))
+}
+
+/// Reads the events of an iCalendar file. Recurrence rules are not expanded,
+/// a recurring event only shows on its first occurrence.
+pub fn parse(s: &str) -> Result<Vec<Event>, Box<dyn Error>> {
+    let mut events = Vec::new();
+    let mut current: Option<Draft> = None;
+
+    for (n, line) in unfold(s) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        // Parameters like `;VALUE=DATE` or `;TZID=...` follow the property name.
+        let name = name.split(';').next().unwrap_or(name);
+        let error = |e: Box<dyn Error>| -> Box<dyn Error> { format!("line {n}: {name}: {e}").into() };
+
+        match (name, value, current.as_mut()) {
+            ("BEGIN", "VEVENT", _) => current = Some(Draft::default()),
+            ("END", "VEVENT", Some(_)) => {
+                let Some(Draft { summary, start: Some((start, time)), end }) = current.take() else {
+                    return Err(format!("line {n}: event without DTSTART").into());
+                };
+                let end = match end {
+                    // DTEND of an all-day event is the day after it.
+                    Some((end, None)) if end > start => end.pred_opt().unwrap_or(end),
+                    Some((end, Some(_))) if end > start => end,
+                    _ => start,
+                };
+                events.push(Event { summary, start, end, time });
+            }
+            ("SUMMARY", _, Some(event)) => event.summary = unescape(value),
+            ("DTSTART", _, Some(event)) => event.start = Some(parse_time(value).map_err(error)?),
+            ("DTEND", _, Some(event)) => event.end = Some(parse_time(value).map_err(error)?),
+            _ => {}
+        }
+    }
+
+    events.sort_by_key(|e| (e.start, e.time));
+    Ok(events)
+}
+
+pub fn load(path: &Path) -> Result<Vec<Event>, Box<dyn Error>> {
+    let s = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    parse(&s).map_err(|e| format!("{}: {e}", path.display()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CALENDAR: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+BEGIN:VEVENT\r
+UID:1\r
+DTSTART;TZID=Europe/Berlin:20261020T093000\r
+DTEND;TZID=Europe/Berlin:20261020T100000\r
+SUMMARY:Stand-up\\, room 2\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:2\r
+DTSTART;VALUE=DATE:20261019\r
+DTEND;VALUE=DATE:20261022\r
+SUMMARY:Office move: pack your\r
+  desk\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+    fn date(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, d).unwrap()
+    }
+
+    #[test]
+    fn events() {
+        let events = parse(CALENDAR).expect("calendar didn't parse");
+        assert_eq!(
+            events,
+            [
+                Event {
+                    summary: "Office move: pack your desk".to_string(),
+                    start: date(19),
+                    end: date(21),
+                    time: None,
+                },
+                Event {
+                    summary: "Stand-up, room 2".to_string(),
+                    start: date(20),
+                    end: date(20),
+                    time: NaiveTime::from_hms_opt(9, 30, 0),
+                },
+            ]
+        );
+        assert!(events[0].occurs_on(date(21)));
+        assert!(!events[0].occurs_on(date(22)));
+    }
+
+    #[test]
+    fn bad_date() {
+        let err = parse("BEGIN:VEVENT\nDTSTART:2026-10-19\nEND:VEVENT").unwrap_err();
+        assert!(err.to_string().starts_with("line 2: DTSTART: "), "{err}");
+    }
+}
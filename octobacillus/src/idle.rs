@@ -0,0 +1,225 @@
+use crate::{logging, process};
+use gtk4::prelude::*;
+use gtk4::{ApplicationWindow, Box as GtkBox, EventControllerLegacy, Overlay, gdk, glib};
+use std::{
+    cell::RefCell,
+    time::{Duration, Instant},
+};
+
+/// `[idle]` settings.
+#[derive(Clone, Debug)]
+pub struct Idle {
+    /// `[idle] dim_after` in seconds without input, never if zero
+    pub dim_after: Duration,
+    /// `[idle] blank_after` in seconds without input, never if zero
+    pub blank_after: Duration,
+    /// `[idle] dim_brightness`, 0.0 (black) to 1.0 (not dimmed at all)
+    pub dim_brightness: f64,
+    /// `[idle] dpms_off`, run when blanking, e.g. to turn the outputs off
+    pub dpms_off: Option<String>,
+    /// `[idle] dpms_on`, run when waking up from blank
+    pub dpms_on: Option<String>,
+}
+
+impl Default for Idle {
+    fn default() -> Self {
+        Self {
+            dim_after: Duration::from_secs(60),
+            blank_after: Duration::from_secs(300),
+            dim_brightness: 0.4,
+            dpms_off: None,
+            dpms_on: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Stage {
+    Awake,
+    Dimmed,
+    Blanked,
+}
+
+/// How far along the greeter is after `idle_for` without input.
+fn stage(idle: &Idle, idle_for: Duration) -> Stage {
+    let reached = |after: Duration| !after.is_zero() && idle_for >= after;
+    if reached(idle.blank_after) {
+        Stage::Blanked
+    } else if reached(idle.dim_after) {
+        Stage::Dimmed
+    } else {
+        Stage::Awake
+    }
+}
+
+struct State {
+    idle: Idle,
+    stage: Stage,
+    last_input: Instant,
+    /// Each window with the shade darkening it.
+    windows: Vec<(glib::WeakRef<ApplicationWindow>, glib::WeakRef<GtkBox>)>,
+    /// What had the focus in each window before blanking hid it.
+    focus: Vec<glib::WeakRef<gtk4::Widget>>,
+    wake_hooks: Vec<Box<dyn Fn()>>,
+}
+
+thread_local! {
+    static STATE: RefCell<State> = RefCell::new(State {
+        idle: Idle::default(),
+        stage: Stage::Awake,
+        last_input: Instant::now(),
+        windows: Vec::new(),
+        focus: Vec::new(),
+        wake_hooks: Vec::new(),
+    });
+}
+
+/// Starts counting idle time. Does nothing if neither dimming nor blanking is on.
+pub fn start(idle: &Idle) {
+    if idle.dim_after.is_zero() && idle.blank_after.is_zero() {
+        return;
+    }
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        state.idle = idle.clone();
+        state.last_input = Instant::now();
+    });
+    glib::timeout_add_seconds_local(1, || {
+        let (idle, idle_for) = STATE.with(|state| {
+            let state = state.borrow();
+            (state.idle.clone(), state.last_input.elapsed())
+        });
+        set_stage(stage(&idle, idle_for));
+        glib::ControlFlow::Continue
+    });
+}
+
+/// Whether animations should hold still, the greeter being dimmed or blank.
+pub fn paused() -> bool {
+    STATE.with(|state| state.borrow().stage != Stage::Awake)
+}
+
+/// Whether nothing is shown at all.
+pub fn blanked() -> bool {
+    STATE.with(|state| state.borrow().stage == Stage::Blanked)
+}
+
+/// Runs `hook` every time the greeter wakes up, to restart what `paused` stopped.
+pub fn on_wake(hook: impl Fn() + 'static) {
+    STATE.with(|state| state.borrow_mut().wake_hooks.push(Box::new(hook)));
+}
+
+/// Dims and blanks `window`, whose content is `overlay`, and wakes up on its input.
+pub fn watch(window: &ApplicationWindow, overlay: &Overlay) {
+    let shade = GtkBox::new(gtk4::Orientation::Vertical, 0);
+    shade.set_widget_name("idle-shade");
+    shade.set_can_target(false);
+    shade.set_visible(false);
+    overlay.add_overlay(&shade);
+
+    let input = EventControllerLegacy::new();
+    input.set_propagation_phase(gtk4::PropagationPhase::Capture);
+    input.connect_event(|_, event| {
+        use gdk::EventType::*;
+        if !matches!(event.event_type(), KeyPress | ButtonPress | MotionNotify | TouchBegin | Scroll) {
+            return glib::Propagation::Proceed;
+        }
+        // The input that wakes a blank screen is not meant for the greeter.
+        let was_blank = blanked();
+        STATE.with(|state| state.borrow_mut().last_input = Instant::now());
+        set_stage(Stage::Awake);
+        if was_blank {
+            glib::Propagation::Stop
+        } else {
+            glib::Propagation::Proceed
+        }
+    });
+    window.add_controller(input);
+
+    STATE.with(|state| state.borrow_mut().windows.push((window.downgrade(), shade.downgrade())));
+}
+
+fn set_stage(stage: Stage) {
+    let (previous, idle) = STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        state.windows.retain(|(window, _)| window.upgrade().is_some());
+        (std::mem::replace(&mut state.stage, stage), state.idle.clone())
+    });
+    if previous == stage {
+        return;
+    }
+    logging::debug("idle", &[("stage", &format!("{stage:?}").to_lowercase())]);
+
+    let windows = STATE.with(|state| state.borrow().windows.clone());
+    for (window, shade) in windows {
+        let (Some(window), Some(shade)) = (window.upgrade(), shade.upgrade()) else {
+            continue;
+        };
+        shade.set_visible(stage == Stage::Dimmed);
+        shade.set_opacity(1.0 - idle.dim_brightness.clamp(0.0, 1.0));
+        // Unmapped, the content stops drawing and its tick callbacks stop too. Hiding
+        // drops the focus, so it is kept to be given back on waking up.
+        if stage == Stage::Blanked
+            && let Some(focus) = GtkWindowExt::focus(&window)
+        {
+            STATE.with(|state| state.borrow_mut().focus.push(focus.downgrade()));
+        }
+        if let Some(child) = window.child() {
+            child.set_visible(stage != Stage::Blanked);
+        }
+        if stage == Stage::Blanked {
+            window.add_css_class("idle-blank");
+        } else {
+            window.remove_css_class("idle-blank");
+        }
+    }
+
+    if previous == Stage::Blanked {
+        let focus = STATE.with(|state| std::mem::take(&mut state.borrow_mut().focus));
+        for widget in focus.iter().filter_map(|widget| widget.upgrade()) {
+            widget.grab_focus();
+        }
+    }
+    if stage == Stage::Blanked {
+        run(idle.dpms_off.as_deref());
+    } else if previous == Stage::Blanked {
+        run(idle.dpms_on.as_deref());
+    }
+    if stage == Stage::Awake {
+        // Taken out while running, so a hook may register another.
+        let hooks = STATE.with(|state| std::mem::take(&mut state.borrow_mut().wake_hooks));
+        hooks.iter().for_each(|hook| hook());
+        STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            let added = std::mem::replace(&mut state.wake_hooks, hooks);
+            state.wake_hooks.extend(added);
+        });
+    }
+}
+
+fn run(cmd: Option<&str>) {
+    let cmd: Vec<String> = cmd.unwrap_or_default().split_whitespace().map(str::to_string).collect();
+    if let Err(e) = process::spawn_detached(&cmd) {
+        logging::error(&format!("could not run {}: {e}", cmd[0]), &[]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stages() {
+        let idle = Idle {
+            dim_after: Duration::from_secs(60),
+            blank_after: Duration::ZERO,
+            ..Idle::default()
+        };
+        assert_eq!(stage(&idle, Duration::from_secs(59)), Stage::Awake);
+        assert_eq!(stage(&idle, Duration::from_secs(600)), Stage::Dimmed);
+
+        let idle = Idle::default();
+        assert_eq!(stage(&idle, Duration::from_secs(60)), Stage::Dimmed);
+        assert_eq!(stage(&idle, Duration::from_secs(300)), Stage::Blanked);
+    }
+}
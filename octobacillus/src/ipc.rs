@@ -0,0 +1,334 @@
+use crate::{
+    auth::{AuthFlow, UiEvent},
+    config::Config,
+    demo::Demo,
+    i18n,
+    logging,
+};
+use greetd_ipc::{Request, Response, codec::SyncCodec};
+use std::{
+    io,
+    os::unix::net::UnixStream,
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, Receiver, Sender},
+    },
+    thread,
+    time::Instant,
+};
+
+/// Where the conversation is held.
+pub enum Backend {
+    /// The real greetd listening on this socket.
+    Greetd(PathBuf),
+    /// An in-process stand-in, see `--demo`.
+    Demo(Demo),
+}
+
+impl Backend {
+    pub fn connect(&self) -> io::Result<UnixStream> {
+        match self {
+            Backend::Greetd(socket) => UnixStream::connect(socket),
+            Backend::Demo(demo) => demo.connect(),
+        }
+    }
+}
+
+/// Picks the fake greetd in demo mode, the real one otherwise. `None` without a socket.
+pub fn backend(config: &Config) -> Option<Backend> {
+    if config.demo.enabled {
+        Some(Backend::Demo(config.demo.clone()))
+    } else {
+        config.socket.clone().map(Backend::Greetd)
+    }
+}
+
+/// Connects to the backend and carries the conversation on a worker thread.
+///
+/// The flow stays shared with the UI so it can be cancelled or inspected
+/// while the worker is blocked on greetd.
+pub fn spawn(backend: Backend, flow: Arc<Mutex<AuthFlow>>, first: Request) -> Receiver<UiEvent> {
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || match backend.connect() {
+        Ok(mut stream) => run(&mut stream, first, &flow, &tx),
+        Err(e) => {
+            let event = flow.lock().unwrap().io_error(i18n::trf("connection_error", &[("error", &e)]));
+            log_event(&event);
+            let _ = tx.send(event);
+        }
+    });
+
+    rx
+}
+
+/// Writes requests and feeds the responses to `flow` until it has nothing more to say.
+pub fn run(stream: &mut UnixStream, first: Request, flow: &Mutex<AuthFlow>, tx: &Sender<UiEvent>) {
+    let mut next_request = Some(first);
+
+    while let Some(request) = next_request.take() {
+        log_request(&request);
+        let response = request
+            .write_to(stream)
+            .map_err(|e| i18n::trf("write_error", &[("error", &e)]))
+            .and_then(|()| Response::read_from(stream).map_err(|e| i18n::trf("response_error", &[("error", &e)])));
+
+        let mut flow = flow.lock().unwrap();
+        let events = match response {
+            Ok(response) => {
+                let (request, events) = flow.handle(response, Instant::now());
+                next_request = request;
+                events
+            }
+            // Losing greetd while cancelling is not worth a message.
+            Err(_) if matches!(request, Request::CancelSession) => Vec::new(),
+            Err(e) => vec![flow.io_error(e)],
+        };
+        drop(flow);
+
+        for event in events {
+            log_event(&event);
+            let _ = tx.send(event);
+        }
+    }
+}
+
+/// Logs what is sent to greetd, but never the answers to its prompts.
+fn log_request(request: &Request) {
+    match request {
+        Request::CreateSession { username } => logging::info("login attempt", &[("user", username)]),
+        Request::PostAuthMessageResponse { response } => {
+            logging::debug("answering prompt", &[("empty", &response.is_none())]);
+        }
+        Request::StartSession { cmd, env } => {
+            logging::info("starting session", &[("cmd", &cmd.join(" "))]);
+            logging::debug("session environment", &[("env", &env.join(" "))]);
+        }
+        Request::CancelSession => logging::info("cancelling session", &[]),
+    }
+}
+
+/// Logs how the conversation went.
+pub fn log_event(event: &UiEvent) {
+    match event {
+        UiEvent::Message(text) => logging::debug("message from greetd", &[("text", text)]),
+        UiEvent::WrongPassword { attempts_left } => {
+            logging::warning("authentication failed", &[("attempts_left", attempts_left)]);
+        }
+        UiEvent::LockedOut(delay) => {
+            logging::warning("too many failed logins, locked out", &[("seconds", &delay.as_secs())]);
+        }
+        UiEvent::Error(text) => logging::error(text, &[]),
+        UiEvent::Started => logging::info("session started", &[]),
+        UiEvent::Cancelled => logging::info("login cancelled", &[]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth;
+    use greetd_ipc::{AuthMessageType, ErrorType};
+    use std::{
+        fs,
+        os::unix::net::UnixListener,
+        sync::atomic::{AtomicUsize, Ordering},
+        thread::JoinHandle,
+    };
+
+    /// A request as seen by the mock greetd, comparable in assertions.
+    #[derive(Debug, PartialEq)]
+    enum Sent {
+        Create(String),
+        Answer(Option<String>),
+        Start(Vec<String>),
+        Cancel,
+    }
+
+    impl From<Request> for Sent {
+        fn from(request: Request) -> Self {
+            match request {
+                Request::CreateSession { username } => Sent::Create(username),
+                Request::PostAuthMessageResponse { response } => Sent::Answer(response),
+                Request::StartSession { cmd, .. } => Sent::Start(cmd),
+                Request::CancelSession => Sent::Cancel,
+            }
+        }
+    }
+
+    fn prompt(auth_message_type: AuthMessageType, text: &str) -> Option<Response> {
+        Some(Response::AuthMessage {
+            auth_message_type,
+            auth_message: text.to_string(),
+        })
+    }
+
+    fn auth_error() -> Option<Response> {
+        Some(Response::Error {
+            error_type: ErrorType::AuthError,
+            description: "pam_authenticate: AUTH_ERR".to_string(),
+        })
+    }
+
+    /// Listens on a fresh socket and answers each request with the next scripted
+    /// response. `None` hangs up instead of answering.
+    fn mock_greetd(script: Vec<Option<Response>>) -> (PathBuf, JoinHandle<Vec<Sent>>) {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "octobacillus-test-{}-{}.sock",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::SeqCst)
+        ));
+        let _ = fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).expect("bind mock greetd");
+        let socket = path.clone();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("greeter never connected");
+            let mut sent = Vec::new();
+            for reply in script {
+                let Ok(request) = Request::read_from(&mut stream) else {
+                    break;
+                };
+                sent.push(Sent::from(request));
+                match reply {
+                    Some(response) => response.write_to(&mut stream).expect("reply to greeter"),
+                    None => break,
+                }
+            }
+            let _ = fs::remove_file(&path);
+            sent
+        });
+
+        (socket, server)
+    }
+
+    fn login(script: Vec<Option<Response>>) -> (Vec<UiEvent>, Vec<Sent>) {
+        let (socket, server) = mock_greetd(script);
+        let flow = Arc::new(Mutex::new(auth::test_flow(3)));
+        let first = flow
+            .lock()
+            .unwrap()
+            .login(auth::test_creds("hunter2"), Instant::now())
+            .expect("login refused");
+        let events = spawn(Backend::Greetd(socket), flow, first).iter().collect();
+        (events, server.join().expect("mock greetd panicked"))
+    }
+
+    #[test]
+    fn success() {
+        let (events, sent) = login(vec![
+            prompt(AuthMessageType::Secret, "Password:"),
+            Some(Response::Success),
+            Some(Response::Success),
+        ]);
+
+        assert!(matches!(&events[..], [UiEvent::Started]));
+        assert_eq!(
+            sent,
+            [
+                Sent::Create("ekah".to_string()),
+                Sent::Answer(Some("hunter2".to_string())),
+                Sent::Start(vec!["niri-session".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn wrong_password() {
+        let (events, sent) = login(vec![
+            prompt(AuthMessageType::Secret, "Password:"),
+            auth_error(),
+            Some(Response::Success),
+        ]);
+
+        assert_eq!(events, [UiEvent::WrongPassword { attempts_left: 2 }]);
+        assert_eq!(
+            sent,
+            [
+                Sent::Create("ekah".to_string()),
+                Sent::Answer(Some("hunter2".to_string())),
+                Sent::Cancel,
+            ]
+        );
+    }
+
+    #[test]
+    fn info_messages() {
+        let (events, sent) = login(vec![
+            prompt(AuthMessageType::Info, "Last login: yesterday"),
+            prompt(AuthMessageType::Secret, "Password:"),
+            prompt(AuthMessageType::Error, "Password expires in 3 days"),
+            Some(Response::Success),
+            Some(Response::Success),
+        ]);
+
+        assert!(matches!(
+            &events[..],
+            [UiEvent::Message(a), UiEvent::Message(b), UiEvent::Started]
+                if a == "Last login: yesterday" && b == "Password expires in 3 days"
+        ));
+        assert_eq!(
+            sent,
+            [
+                Sent::Create("ekah".to_string()),
+                Sent::Answer(None),
+                Sent::Answer(Some("hunter2".to_string())),
+                Sent::Answer(None),
+                Sent::Start(vec!["niri-session".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn multiple_secrets() {
+        let (events, sent) = login(vec![
+            prompt(AuthMessageType::Visible, "login:"),
+            prompt(AuthMessageType::Secret, "Password:"),
+            prompt(AuthMessageType::Secret, "Verification code:"),
+            Some(Response::Success),
+            Some(Response::Success),
+        ]);
+
+        assert!(matches!(&events[..], [UiEvent::Started]));
+        assert_eq!(
+            sent,
+            [
+                Sent::Create("ekah".to_string()),
+                Sent::Answer(Some("ekah".to_string())),
+                Sent::Answer(Some("hunter2".to_string())),
+                Sent::Answer(Some("hunter2".to_string())),
+                Sent::Start(vec!["niri-session".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn socket_drop() {
+        let (events, sent) = login(vec![prompt(AuthMessageType::Secret, "Password:"), None]);
+
+        assert!(matches!(&events[..], [UiEvent::Error(e)] if e.starts_with("Response error")));
+        assert_eq!(
+            sent,
+            [
+                Sent::Create("ekah".to_string()),
+                Sent::Answer(Some("hunter2".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn greetd_error() {
+        let (events, sent) = login(vec![
+            Some(Response::Error {
+                error_type: ErrorType::Error,
+                description: "a session is already being configured".to_string(),
+            }),
+            Some(Response::Success),
+        ]);
+
+        assert!(matches!(&events[..], [UiEvent::Error(e)] if e.contains("already being configured")));
+        assert_eq!(sent, [Sent::Create("ekah".to_string()), Sent::Cancel]);
+    }
+}
@@ -0,0 +1,131 @@
+use crate::sysinfo;
+use chrono::{DateTime, Local};
+use std::{collections::HashMap, env, fs, path::Path};
+
+/// What the escapes of an `/etc/issue` file stand for, see agetty(8).
+#[derive(Clone, Debug)]
+pub struct Host {
+    pub hostname: String,
+    pub domain: String,
+    pub kernel_release: String,
+    pub kernel_version: String,
+    pub machine: String,
+    /// `/etc/os-release`, for `\S` and `\S{KEY}`
+    pub os_release: HashMap<String, String>,
+    pub now: DateTime<Local>,
+}
+
+impl Host {
+    pub fn current() -> Self {
+        let proc = Path::new(sysinfo::PROC);
+        let read = |path: &str| fs::read_to_string(proc.join(path)).map(|s| s.trim().to_string()).unwrap_or_default();
+        let os_release = ["/etc/os-release", "/usr/lib/os-release"]
+            .into_iter()
+            .find_map(|path| fs::read_to_string(path).ok())
+            .map(|s| os_release(&s))
+            .unwrap_or_default();
+        Self {
+            hostname: sysinfo::hostname(proc).unwrap_or_default(),
+            domain: read("sys/kernel/domainname"),
+            kernel_release: sysinfo::kernel(proc).unwrap_or_default(),
+            kernel_version: read("sys/kernel/version"),
+            machine: env::consts::ARCH.to_string(),
+            os_release,
+            now: Local::now(),
+        }
+    }
+}
+
+/// The `KEY=value` lines of os-release(5), values unquoted.
+pub fn os_release(s: &str) -> HashMap<String, String> {
+    s.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| {
+            let value = if value.starts_with(['"', '\'']) {
+                enquote::unquote(value).unwrap_or_else(|_| value.to_string())
+            } else {
+                value.to_string()
+            };
+            (key.trim().to_string(), value)
+        })
+        .collect()
+}
+
+/// Fills in the escapes of an issue file. Those meaning nothing on a graphical
+/// greeter, like the tty (`\l`) or colors (`\e{red}`), become empty, and
+/// unknown ones stay as they are.
+pub fn expand(text: &str, host: &Host) -> String {
+    let mut expanded = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            expanded.push(c);
+            continue;
+        }
+        let Some(escape) = chars.next() else {
+            expanded.push('\\');
+            break;
+        };
+        // `\S{KEY}`, `\e{color}`, `\4{interface}` and the like take an argument.
+        let mut argument = None;
+        if chars.peek() == Some(&'{') {
+            chars.next();
+            argument = Some(chars.by_ref().take_while(|&c| c != '}').collect::<String>());
+        }
+        let os = |key: &str| host.os_release.get(key).cloned().unwrap_or_default();
+        match escape {
+            '\\' => expanded.push('\\'),
+            'd' => expanded.push_str(&host.now.format("%a %b %e %Y").to_string()),
+            't' => expanded.push_str(&host.now.format("%H:%M:%S").to_string()),
+            'n' => expanded.push_str(&host.hostname),
+            'o' => expanded.push_str(&host.domain),
+            'r' => expanded.push_str(&host.kernel_release),
+            'v' => expanded.push_str(&host.kernel_version),
+            'm' => expanded.push_str(&host.machine),
+            's' => expanded.push_str("Linux"),
+            'S' => match argument {
+                Some(key) => expanded.push_str(&os(&key)),
+                None => {
+                    let name = os("PRETTY_NAME");
+                    expanded.push_str(if name.is_empty() { "Linux" } else { &name });
+                }
+            },
+            'e' | 'l' | 'b' | 'u' | 'U' | '4' | '6' => {}
+            other => {
+                expanded.push('\\');
+                expanded.push(other);
+                if let Some(argument) = argument {
+                    expanded.push_str(&format!("{{{argument}}}"));
+                }
+            }
+        }
+    }
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn escapes() {
+        let host = Host {
+            hostname: "octopus".to_string(),
+            domain: "(none)".to_string(),
+            kernel_release: "6.9.1-arch1-1".to_string(),
+            kernel_version: "#1 SMP PREEMPT_DYNAMIC".to_string(),
+            machine: "x86_64".to_string(),
+            os_release: os_release("# comment\nNAME=\"Arch Linux\"\nPRETTY_NAME=\"Arch Linux\"\nID=arch\n"),
+            now: Local.with_ymd_and_hms(2026, 10, 19, 9, 5, 0).unwrap(),
+        };
+        assert_eq!(
+            expand("\\e{bold}\\S\\e{reset} \\r (\\l) on \\n", &host),
+            "Arch Linux 6.9.1-arch1-1 () on octopus"
+        );
+        assert_eq!(expand("\\S{ID} \\m on \\d at \\t", &host), "arch x86_64 on Mon Oct 19 2026 at 09:05:00");
+        assert_eq!(expand("100\\% \\\\ \\x{y} \\", &host), "100\\% \\ \\x{y} \\");
+    }
+}
@@ -0,0 +1,105 @@
+use crate::{i18n, logging, process};
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Button, Label, Orientation, gdk};
+use std::{cell::RefCell, rc::Rc};
+
+/// `[keyboard]` settings.
+#[derive(Clone, Debug)]
+pub struct Keyboard {
+    /// `[keyboard] lock_indicator`, warns about Caps Lock and shows Num Lock
+    pub lock_indicator: bool,
+    /// `[keyboard] layouts`, comma separated XKB layout names offered by the switcher
+    pub layouts: Vec<String>,
+    /// `[keyboard] switch_command`, run on switching with `{layout}` and `{index}` replaced
+    pub switch_command: Option<String>,
+}
+
+impl Default for Keyboard {
+    fn default() -> Self {
+        Self {
+            lock_indicator: true,
+            layouts: Vec::new(),
+            switch_command: None,
+        }
+    }
+}
+
+/// Caps Lock warning and Num Lock indicator, kept up to date from the seat's keyboard.
+pub fn lock_indicator(keyboard: &Keyboard) -> Option<GtkBox> {
+    if !keyboard.lock_indicator {
+        return None;
+    }
+    let device = gdk::Display::default()?.default_seat()?.keyboard()?;
+
+    let indicator = GtkBox::new(Orientation::Horizontal, 10);
+    indicator.set_halign(gtk4::Align::Center);
+
+    let caps = Label::new(None);
+    caps.set_widget_name("caps-lock");
+    caps.set_visible(device.is_caps_locked());
+    indicator.append(&caps);
+
+    let num = Label::new(None);
+    num.set_widget_name("num-lock");
+    num.set_visible(device.is_num_locked());
+    indicator.append(&num);
+
+    {
+        let (caps, num) = (caps.downgrade(), num.downgrade());
+        i18n::on_change(move || {
+            if let (Some(caps), Some(num)) = (caps.upgrade(), num.upgrade()) {
+                caps.set_text(&i18n::tr("caps_lock"));
+                num.set_text(&i18n::tr("num_lock"));
+            }
+        });
+    }
+
+    device.connect_caps_lock_state_notify(move |device| caps.set_visible(device.is_caps_locked()));
+    device.connect_num_lock_state_notify(move |device| num.set_visible(device.is_num_locked()));
+
+    Some(indicator)
+}
+
+/// A button showing the selected layout, cycling through `layouts` when clicked.
+/// The layout shown is kept in `selected`, for the session's environment.
+pub fn layout_switcher(keyboard: &Keyboard, selected: &Rc<RefCell<Option<String>>>) -> Option<Button> {
+    let first = keyboard.layouts.first()?;
+    *selected.borrow_mut() = Some(first.clone());
+
+    let button = Button::with_label(first);
+    button.set_widget_name("layout");
+    {
+        let button = button.downgrade();
+        i18n::on_change(move || {
+            if let Some(button) = button.upgrade() {
+                button.set_tooltip_text(Some(&i18n::tr("keyboard_layout")));
+            }
+        });
+    }
+
+    let layouts = keyboard.layouts.clone();
+    let switch_command = keyboard.switch_command.clone();
+    let selected = selected.clone();
+    button.connect_clicked(move |button| {
+        let index = layouts
+            .iter()
+            .position(|l| Some(l) == selected.borrow().as_ref())
+            .map_or(0, |i| (i + 1) % layouts.len());
+        let layout = &layouts[index];
+
+        if let Some(cmd) = &switch_command {
+            let cmd = cmd.replace("{layout}", layout).replace("{index}", &index.to_string());
+            let cmd: Vec<String> = cmd.split_whitespace().map(str::to_string).collect();
+            if let Err(e) = process::spawn_detached(&cmd) {
+                logging::error(&format!("could not switch layout with {}: {e}", cmd[0]), &[]);
+                return;
+            }
+        }
+
+        logging::debug("keyboard layout", &[("layout", layout)]);
+        button.set_label(layout);
+        *selected.borrow_mut() = Some(layout.clone());
+    });
+
+    Some(button)
+}
@@ -0,0 +1,101 @@
+use std::time::{Duration, Instant};
+
+/// Counts failed logins and locks the greeter out for a while once they run out.
+///
+/// Every lockout in a row doubles the wait, up to `max_delay`. A successful
+/// login (or simply waiting out a lockout) gives the full set of attempts back,
+/// but only a successful login resets the backoff.
+pub struct Lockout {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    attempts_left: u32,
+    lockouts: u32,
+    until: Option<Instant>,
+}
+
+impl Lockout {
+    pub fn new(max_attempts: u32, base_delay: Duration, max_delay: Duration) -> Self {
+        let max_attempts = max_attempts.max(1);
+        Self {
+            max_attempts,
+            base_delay,
+            max_delay,
+            attempts_left: max_attempts,
+            lockouts: 0,
+            until: None,
+        }
+    }
+
+    pub fn attempts_left(&self) -> u32 {
+        self.attempts_left
+    }
+
+    /// Time left until the greeter accepts passwords again, if locked.
+    pub fn remaining(&mut self, now: Instant) -> Option<Duration> {
+        let until = self.until?;
+        if now >= until {
+            self.until = None;
+            self.attempts_left = self.max_attempts;
+            return None;
+        }
+        Some(until - now)
+    }
+
+    /// Registers a wrong password. Returns the lockout duration if this was the last attempt.
+    pub fn record_failure(&mut self, now: Instant) -> Option<Duration> {
+        self.attempts_left = self.attempts_left.saturating_sub(1);
+        if self.attempts_left > 0 {
+            return None;
+        }
+
+        let factor = 2u32.saturating_pow(self.lockouts);
+        let delay = self.base_delay.saturating_mul(factor).min(self.max_delay);
+        self.lockouts += 1;
+        self.until = Some(now + delay);
+        Some(delay)
+    }
+
+    pub fn record_success(&mut self) {
+        self.attempts_left = self.max_attempts;
+        self.lockouts = 0;
+        self.until = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locks_after_max_attempts() {
+        let now = Instant::now();
+        let mut lockout = Lockout::new(3, Duration::from_secs(30), Duration::from_secs(600));
+
+        assert_eq!(lockout.record_failure(now), None);
+        assert_eq!(lockout.record_failure(now), None);
+        assert_eq!(lockout.attempts_left(), 1);
+        assert_eq!(lockout.record_failure(now), Some(Duration::from_secs(30)));
+        assert_eq!(lockout.remaining(now), Some(Duration::from_secs(30)));
+        assert_eq!(lockout.remaining(now + Duration::from_secs(30)), None);
+        assert_eq!(lockout.attempts_left(), 3);
+    }
+
+    #[test]
+    fn backoff_doubles_and_caps() {
+        let mut now = Instant::now();
+        let mut lockout = Lockout::new(1, Duration::from_secs(30), Duration::from_secs(100));
+
+        let mut delays = Vec::new();
+        for _ in 0..4 {
+            let delay = lockout.record_failure(now).expect("single attempt should lock");
+            delays.push(delay.as_secs());
+            now += delay;
+            assert_eq!(lockout.remaining(now), None);
+        }
+        assert_eq!(delays, [30, 60, 100, 100]);
+
+        lockout.record_success();
+        assert_eq!(lockout.record_failure(now), Some(Duration::from_secs(30)));
+    }
+}
@@ -0,0 +1,250 @@
+use crate::config::LogTarget;
+use chrono::Local;
+use std::{
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    os::unix::net::UnixDatagram,
+    path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+/// `[log]` settings.
+#[derive(Clone, Debug)]
+pub struct Log {
+    /// `[log] target`
+    pub target: LogTarget,
+    /// `[log] file`, written when `target` is `file`
+    pub file: PathBuf,
+    /// `[log] max_size` in KiB, after which the file is rotated
+    pub max_size: u64,
+    /// `[log] keep`, rotated files kept besides the current one
+    pub keep: usize,
+    /// `[log] debug`, or `--debug`: also log every greetd request and other details
+    pub debug: bool,
+}
+
+impl Default for Log {
+    fn default() -> Self {
+        Self {
+            target: LogTarget::Auto,
+            file: PathBuf::from("/var/log/octobacillus/greeter.log"),
+            max_size: 1024,
+            keep: 3,
+            debug: false,
+        }
+    }
+}
+
+const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Severity, with the syslog priority journald files it under.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Level {
+    Error = 3,
+    Warning = 4,
+    Info = 6,
+    Debug = 7,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+            Level::Info => "info",
+            Level::Debug => "debug",
+        })
+    }
+}
+
+enum Sink {
+    Stderr,
+    Journal(UnixDatagram),
+    File { path: PathBuf, file: File, max_size: u64, keep: usize },
+}
+
+/// Until `init` runs, which needs the config, everything goes to stderr.
+static SINK: Mutex<Sink> = Mutex::new(Sink::Stderr);
+static DEBUG: AtomicBool = AtomicBool::new(false);
+
+/// Sends the log where `[log]` says. A target that cannot be opened falls back to stderr.
+pub fn init(settings: &Log) {
+    DEBUG.store(settings.debug, Ordering::Relaxed);
+
+    let journal = || {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(JOURNAL_SOCKET)?;
+        Ok::<_, io::Error>(Sink::Journal(socket))
+    };
+    let sink = match settings.target {
+        LogTarget::Stderr => Ok(Sink::Stderr),
+        LogTarget::Journald => journal(),
+        LogTarget::Auto if Path::new(JOURNAL_SOCKET).exists() => journal(),
+        LogTarget::Auto => Ok(Sink::Stderr),
+        LogTarget::File => open(&settings.file).map(|file| Sink::File {
+            path: settings.file.clone(),
+            file,
+            max_size: settings.max_size * 1024,
+            keep: settings.keep,
+        }),
+    };
+    match sink {
+        Ok(sink) => *SINK.lock().unwrap() = sink,
+        Err(e) => log(Level::Error, &format!("cannot log to {:?} ({e}), using stderr", settings.target), &[]),
+    }
+}
+
+fn open(path: &Path) -> io::Result<File> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Records `message` with `fields`, which journald keeps as fields of their own
+/// and the other targets append as `key=value`. Debug messages are dropped
+/// unless debugging is on.
+pub fn log(level: Level, message: &str, fields: &[(&str, &dyn fmt::Display)]) {
+    if level == Level::Debug && !DEBUG.load(Ordering::Relaxed) {
+        return;
+    }
+    let mut sink = SINK.lock().unwrap();
+    let written = match &mut *sink {
+        Sink::Stderr => {
+            eprintln!("octobacillus: {}", line(level, message, fields));
+            Ok(())
+        }
+        Sink::Journal(socket) => socket.send(&journal_entry(level, message, fields)).map(drop),
+        Sink::File { path, file, max_size, keep } => {
+            let stamp = Local::now().format("%Y-%m-%dT%H:%M:%S%.3f");
+            writeln!(file, "{stamp} {}", line(level, message, fields)).and_then(|()| {
+                if file.metadata()?.len() >= *max_size {
+                    rotate(path, *keep)?;
+                    *file = open(path)?;
+                }
+                Ok(())
+            })
+        }
+    };
+    // Nowhere better to complain, and a greeter has to keep going.
+    if let Err(e) = written {
+        eprintln!("octobacillus: logging failed ({e}): {}", line(level, message, fields));
+    }
+}
+
+pub fn error(message: &str, fields: &[(&str, &dyn fmt::Display)]) {
+    log(Level::Error, message, fields);
+}
+
+pub fn warning(message: &str, fields: &[(&str, &dyn fmt::Display)]) {
+    log(Level::Warning, message, fields);
+}
+
+pub fn info(message: &str, fields: &[(&str, &dyn fmt::Display)]) {
+    log(Level::Info, message, fields);
+}
+
+pub fn debug(message: &str, fields: &[(&str, &dyn fmt::Display)]) {
+    log(Level::Debug, message, fields);
+}
+
+/// `warning: message key=value`, quoting values with spaces.
+fn line(level: Level, message: &str, fields: &[(&str, &dyn fmt::Display)]) -> String {
+    let mut line = format!("{level}: {message}");
+    for (key, value) in fields {
+        let value = value.to_string();
+        if value.is_empty() || value.contains(char::is_whitespace) {
+            line.push_str(&format!(" {}={value:?}", key.to_lowercase()));
+        } else {
+            line.push_str(&format!(" {}={value}", key.to_lowercase()));
+        }
+    }
+    line
+}
+
+/// One datagram of journald's native protocol. Values with a newline take the
+/// binary form: the key, a newline, the length as 64-bit little endian, the value.
+fn journal_entry(level: Level, message: &str, fields: &[(&str, &dyn fmt::Display)]) -> Vec<u8> {
+    let mut entry = Vec::new();
+    let mut field = |key: &str, value: &str| {
+        entry.extend_from_slice(key.as_bytes());
+        if value.contains('\n') {
+            entry.push(b'\n');
+            entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        } else {
+            entry.push(b'=');
+        }
+        entry.extend_from_slice(value.as_bytes());
+        entry.push(b'\n');
+    };
+    field("MESSAGE", message);
+    field("PRIORITY", &(level as u8).to_string());
+    field("SYSLOG_IDENTIFIER", "octobacillus");
+    for (key, value) in fields {
+        // Journal field names are upper case letters, digits and underscores.
+        let key: String = key
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+            .collect();
+        field(&format!("OCTOBACILLUS_{key}"), &value.to_string());
+    }
+    entry
+}
+
+/// `greeter.log` → `greeter.log.1` → … → `greeter.log.<keep>`, the oldest dropped.
+fn rotate(path: &Path, keep: usize) -> io::Result<()> {
+    let numbered = |n: usize| {
+        let mut name = path.as_os_str().to_owned();
+        name.push(format!(".{n}"));
+        PathBuf::from(name)
+    };
+    if keep == 0 {
+        return fs::remove_file(path);
+    }
+    for n in (1..keep).rev() {
+        match fs::rename(numbered(n), numbered(n + 1)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+    }
+    fs::rename(path, numbered(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats() {
+        let user = "ekah";
+        let fields: [(&str, &dyn fmt::Display); 2] = [("user", &user), ("cmd", &"niri-session --debug")];
+        assert_eq!(
+            line(Level::Info, "login attempt", &fields),
+            "info: login attempt user=ekah cmd=\"niri-session --debug\""
+        );
+        assert_eq!(
+            journal_entry(Level::Warning, "two\nlines", &fields[..1]),
+            b"MESSAGE\n\x09\0\0\0\0\0\0\0two\nlines\nPRIORITY=4\nSYSLOG_IDENTIFIER=octobacillus\nOCTOBACILLUS_USER=ekah\n"
+        );
+    }
+
+    #[test]
+    fn rotates() {
+        let dir = std::env::temp_dir().join(format!("octobacillus-log-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("greeter.log");
+        for round in ["first", "second", "third"] {
+            fs::write(&path, round).unwrap();
+            rotate(&path, 2).unwrap();
+        }
+        assert!(!path.exists());
+        assert_eq!(fs::read_to_string(dir.join("greeter.log.1")).unwrap(), "third");
+        assert_eq!(fs::read_to_string(dir.join("greeter.log.2")).unwrap(), "second");
+        assert!(!dir.join("greeter.log.3").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,40 +1,93 @@
-use gtk4::{prelude::*, Grid};
-use gtk4::{Application, ApplicationWindow, Box as GtkBox, Orientation, Entry, prelude::EntryExt, Label, CssProvider, glib, EventControllerKey, EventControllerMotion, Picture, Overlay, Button};
-use gtk4_layer_shell::{Edge, LayerShell};
-use greetd_ipc::{Request, Response, AuthMessageType, codec::SyncCodec};
+use gtk4::prelude::*;
+use gtk4::{gdk, Application, ApplicationWindow, Box as GtkBox, Orientation, Entry, prelude::EntryExt, Label, glib, EventControllerKey, EventControllerMotion, Overlay, Button};
+use gtk4_layer_shell::{Edge, KeyboardMode, Layer, LayerShell as _};
 use std::{
     env,
     fs,
-    os::unix::net::UnixStream,
     rc::Rc,
-    time::Instant,
+    sync::{Arc, Mutex, mpsc},
+    time::{Duration, Instant},
 };
-use chrono::{Datelike, Local};
+use chrono::Local;
 use std::cell::{Cell, RefCell};
 use std::f64::consts::PI;
-use rodio::{Decoder, OutputStream, Sink};
-use std::fs::File;
-use std::io::BufReader;
-use std::thread;
-use std::io::{self, BufRead};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use getopts::Options;
+
+mod accessibility;
+mod auth;
+mod background;
+mod banner;
+mod calendar;
+mod config;
+mod demo;
+mod i18n;
+mod ics;
+mod idle;
+mod issue;
+mod ipc;
+mod keyboard;
+mod lockout;
+mod logging;
+mod osk;
+mod power;
+mod probe;
+mod process;
+mod sound;
+mod statusbar;
+mod sysinfo;
+mod theme;
+mod tui;
+
+use auth::{AuthFlow, Credentials, Refused, State, UiEvent};
+use config::{Choices, Config, KeyboardInteractivity, LayerShell};
+use lockout::Lockout;
+use probe::Probe;
+use sound::Player;
 
 fn make_label_bouncy(label: &Label, amplitude: f64, speed: f64) {
-    let label_clone = label.clone();
+    if amplitude == 0.0 {
+        return;
+    }
     let start_time = Instant::now();
+    // Unmapped by blanking, the callback is not called and so never sees `paused`.
+    // It is still there, and carries on once the label is shown again.
+    let running = Rc::new(Cell::new(false));
 
-    label.add_tick_callback(move |_, _| {
-        let elapsed = start_time.elapsed().as_secs_f64();
-        let offset = (elapsed * speed * 2.0 * PI).sin() * amplitude;
+    let bounce = move |label: &Label| {
+        if running.replace(true) {
+            return;
+        }
+        let running = running.clone();
+        label.add_tick_callback(move |label, _| {
+            // Stopped while idle, waking up starts it again.
+            if idle::paused() {
+                running.set(false);
+                return glib::ControlFlow::Break;
+            }
+            let elapsed = start_time.elapsed().as_secs_f64();
+            let offset = (elapsed * speed * 2.0 * PI).sin() * amplitude;
 
-        label_clone.set_margin_top(offset.max(0.0) as i32); // Prevent negative margin
-        label_clone.set_margin_bottom((-offset).max(0.0) as i32);
+            label.set_margin_top(offset.max(0.0) as i32); // Prevent negative margin
+            label.set_margin_bottom((-offset).max(0.0) as i32);
 
-        glib::ControlFlow::Continue
+            glib::ControlFlow::Continue
+        });
+    };
+    bounce(label);
+    let label = label.downgrade();
+    idle::on_wake(move || {
+        if let Some(label) = label.upgrade() {
+            bounce(&label);
+        }
     });
 }
 
 fn typing_effect(label: &Label, text: &str, delay_ms: u64) {
+    if delay_ms == 0 {
+        label.set_text(text);
+        return;
+    }
     let label = label.clone();
     let chars: Vec<char> = text.chars().collect();
     let index = Rc::new(RefCell::new(0));
@@ -55,31 +108,35 @@ fn typing_effect(label: &Label, text: &str, delay_ms: u64) {
     });
 }
 
-fn read_username_from_file() -> Option<String> {
-    let path = "/usr/share/octobacillus/user.octo";
+fn read_username_from_file(path: &Path) -> Option<String> {
     let content = fs::read_to_string(path).ok()?;
     for line in content.lines() {
-        if let Some(rest) = line.strip_prefix("name") {
-            if let Some(eq_value) = rest.split('=').nth(1) {
-                return Some(eq_value.trim().to_string());
-            }
+        if let Some(rest) = line.strip_prefix("name")
+            && let Some(eq_value) = rest.split('=').nth(1)
+        {
+            return Some(eq_value.trim().to_string());
         }
     }
     None
 }
 
-fn fade_out_and_quit(window: &ApplicationWindow) {
+/// Longest the login sound may keep the greeter from quitting.
+const LOGIN_SOUND_MAX: Duration = Duration::from_secs(3);
+
+fn fade_out_and_quit(window: &ApplicationWindow, duration: Duration, player: &Player) {
     let win_clone = window.clone();
+    let player = player.clone();
     let start_time = Instant::now();
 
     window.add_tick_callback(move |_, _| {
         let elapsed = start_time.elapsed().as_millis();
-        let t = (elapsed as f64 / 500.0).min(1.0); // duration: 500ms
+        let t = (elapsed as f64 / duration.as_millis().max(1) as f64).min(1.0);
         let eased = ease_in_out_cubic(1.0 - t); // fade from 1.0 to 0.0
 
         win_clone.set_opacity(eased);
 
         if t >= 1.0 {
+            player.drain(LOGIN_SOUND_MAX);
             std::process::exit(0);
         }
 
@@ -95,72 +152,295 @@ fn ease_in_out_cubic(t: f64) -> f64 {
     }
 }
 
-fn get_startup_sound_path(config_path: &str) -> Option<String> {
-    let file = File::open(config_path).ok()?;
-    let reader = io::BufReader::new(file);
-    let mut in_set_block = false;
+/// The startup sound named by cynager's info.probe, relative to `[paths] startup_sounds`.
+fn probe_startup_sound(config: &Config) -> Option<PathBuf> {
+    let probe = Probe::load(&config.probe)
+        .map_err(|e| logging::warning(&e.to_string(), &[]))
+        .ok()?;
+    let name = probe.get("startup").filter(|name| !name.is_empty())?;
+    Some(config.startup_sounds.join(name))
+}
+
+fn shake(entry: &Entry) {
+    entry.add_css_class("shake-error");
+    entry.set_text("");
+    glib::timeout_add_local(std::time::Duration::from_millis(400), {
+        let entry_weak = entry.downgrade();
+        move || {
+            if let Some(entry) = entry_weak.upgrade() {
+                entry.remove_css_class("shake-error");
+            }
+            glib::ControlFlow::Break
+        }
+    });
+}
 
-    for line in reader.lines().map_while(Result::ok) {
-        let trimmed = line.trim();
+fn show_lockout(flow: &Arc<Mutex<AuthFlow>>, entry: &Entry, status: &Label) {
+    let flow = flow.clone();
+    let entry = entry.clone();
+    let status = status.clone();
 
-        if trimmed == ":set" {
-            in_set_block = true;
-            continue;
+    let tick = move || match flow.lock().unwrap().locked_out(Instant::now()) {
+        Some(left) => {
+            entry.set_sensitive(false);
+            status.set_text(&UiEvent::LockedOut(left).describe());
+            glib::ControlFlow::Continue
         }
-        if trimmed == ":end" {
-            in_set_block = false;
-            continue;
+        None => {
+            status.set_text("");
+            entry.set_sensitive(true);
+            entry.grab_focus();
+            glib::ControlFlow::Break
         }
+    };
+
+    if tick().is_continue() {
+        glib::timeout_add_local(Duration::from_secs(1), tick);
+    }
+}
 
-        if in_set_block && trimmed.starts_with("startup") {
-            // Split by ':' and take the second part (the filename)
-            let parts: Vec<&str> = trimmed.split(':').collect();
-            if parts.len() >= 2 {
-                let filename = parts[1].trim();
-                
-                // Construct the absolute path
-                let mut path = PathBuf::from("/var/lib/cynager/niri/sound/startup/");
-                path.push(filename);
-                
-                return Some(path.to_string_lossy().into_owned());
+fn mute_button(player: &Player) -> Button {
+    let button = Button::with_label("🔊");
+    button.set_widget_name("mute");
+    let relabel = {
+        let button = button.downgrade();
+        let player = player.clone();
+        move || {
+            if let Some(button) = button.upgrade() {
+                let muted = player.muted();
+                let name = i18n::tr(if muted { "unmute" } else { "mute" });
+                button.set_label(if muted { "🔇" } else { "🔊" });
+                button.set_tooltip_text(Some(&name));
+                button.update_property(&[gtk4::accessible::Property::Label(&name)]);
             }
         }
+    };
+
+    // Shown once the audio thread has opened an output device.
+    button.set_visible(false);
+    let button_weak = button.downgrade();
+    let audio = player.clone();
+    glib::timeout_add_local(Duration::from_millis(100), move || {
+        let Some(button) = button_weak.upgrade() else {
+            return glib::ControlFlow::Break;
+        };
+        match audio.available() {
+            Some(available) => {
+                button.set_visible(available);
+                glib::ControlFlow::Break
+            }
+            None => glib::ControlFlow::Continue,
+        }
+    });
+
+    let player = player.clone();
+    let toggle = relabel.clone();
+    button.connect_clicked(move |_| {
+        player.set_muted(!player.muted());
+        toggle();
+    });
+    i18n::on_change(relabel);
+    button
+}
+
+/// A button cycling through `[i18n] languages`. Picking one translates the greeter
+/// and becomes the session's `LANG`, stored in `selected`.
+fn language_picker(config: &Config, selected: &Rc<RefCell<Option<String>>>) -> Option<Button> {
+    let languages = config.languages.clone();
+    let current = i18n::env_locale();
+    let first = match config.language.clone().or(current).filter(|l| languages.contains(l)) {
+        Some(first) => first,
+        None => {
+            // Not offered, the greeter and the session switch to what the button shows.
+            let first = languages.first()?.clone();
+            i18n::select(&first, &config.locales);
+            *selected.borrow_mut() = Some(first.clone());
+            first
+        }
+    };
+
+    let button = Button::with_label(&i18n::language_name(&first, &config.locales));
+    button.set_widget_name("language");
+    {
+        let button = button.downgrade();
+        i18n::on_change(move || {
+            if let Some(button) = button.upgrade() {
+                button.set_tooltip_text(Some(&i18n::tr("language")));
+            }
+        });
     }
-    None
+
+    let shown = Rc::new(RefCell::new(first));
+    let locales = config.locales.clone();
+    let selected = selected.clone();
+    button.connect_clicked(move |button| {
+        let index = languages
+            .iter()
+            .position(|l| *l == *shown.borrow())
+            .map_or(0, |i| (i + 1) % languages.len());
+        let language = languages[index].clone();
+
+        button.set_label(&i18n::language_name(&language, &locales));
+        i18n::select(&language, &locales);
+        *shown.borrow_mut() = language.clone();
+        *selected.borrow_mut() = Some(language);
+    });
+    Some(button)
+}
+
+fn print_usage(program: &str, opts: Options) {
+    let brief = format!("Usage: {program} [options]");
+    print!("{}", opts.usage(&brief));
 }
 
 fn main() {
+    let args: Vec<String> = env::args().collect();
+    let program = args[0].clone();
+
+    let mut opts = Options::new();
+    opts.optflag("h", "help", "print this help menu");
+    opts.optflag("v", "version", "print the version and exit");
+    opts.optopt("c", "config", "config file to use", "PATH");
+    opts.optopt("", "cmd", "command to start the session with", "CMD");
+    opts.optmulti("e", "env", "extra environment for the session", "KEY=VAL");
+    opts.optflag("d", "demo", "talk to a fake greetd, for trying out themes");
+    opts.optflag("", "no-sound", "do not play any sounds");
+    opts.optflag("", "text", "greet on the terminal instead of starting GTK");
+    opts.optopt("t", "theme", "theme name, or path to a theme directory or CSS file", "THEME");
+    opts.optflag("", "hot-reload", "reload the theme when its files change");
+    opts.optopt("s", "socket", "greetd socket, overrides GREETD_SOCK", "PATH");
+    opts.optflag("", "debug", "log greetd requests and other details");
+
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => {
+            eprintln!("octobacillus: {f}");
+            print_usage(&program, opts);
+            std::process::exit(1);
+        }
+    };
+
+    if matches.opt_present("help") {
+        print_usage(&program, opts);
+        std::process::exit(0);
+    }
+    if matches.opt_present("version") {
+        println!("octobacillus {}", env!("CARGO_PKG_VERSION"));
+        std::process::exit(0);
+    }
+
+    let config_path = matches
+        .opt_str("config")
+        .unwrap_or_else(|| config::DEFAULT_CONFIG_PATH.to_string());
+    // Defaults in place of an unreadable file could drop the banner or the lockout
+    // settings without anyone noticing, better not to start at all.
+    let mut config = match Config::load(&config_path) {
+        Ok(config) => config,
+        Err(e) => {
+            logging::init(&Config::default().log);
+            logging::error(&e.to_string(), &[("config", &config_path)]);
+            std::process::exit(1);
+        }
+    };
+    if matches.opt_present("debug") {
+        config.log.debug = true;
+    }
+    logging::init(&config.log);
+    logging::info("starting", &[("version", &env!("CARGO_PKG_VERSION")), ("config", &config_path)]);
+    logging::debug("config loaded", &[("config", &config_path)]);
+    for problem in &config.skipped {
+        logging::warning(&format!("{problem}, using the default"), &[("config", &config_path)]);
+    }
+
+    if let Some(cmd) = matches.opt_str("cmd") {
+        config.command = cmd.split_whitespace().map(str::to_string).collect();
+        if config.command.is_empty() {
+            logging::error("--cmd must not be empty", &[]);
+            std::process::exit(1);
+        }
+    }
+    for pair in matches.opt_strs("env") {
+        if let Err(e) = config.set_env(&pair) {
+            logging::error(&format!("--env {e}"), &[]);
+            std::process::exit(1);
+        }
+    }
+    if matches.opt_present("demo") {
+        config.demo.enabled = true;
+    }
+    if matches.opt_present("no-sound") {
+        config.sound.enabled = false;
+    }
+    if let Some(theme) = matches.opt_str("theme") {
+        config.theme = Some(theme);
+    }
+    if matches.opt_present("hot-reload") {
+        config.hot_reload = true;
+    }
+    config.socket = matches
+        .opt_str("socket")
+        .or_else(|| env::var("GREETD_SOCK").ok())
+        .map(PathBuf::from);
+
+    if !config.accessibility.animations {
+        config.bounce_amplitude = 0.0;
+        config.typing_delay = 0;
+        config.fade_duration = Duration::ZERO;
+        config.background.crossfade = Duration::ZERO;
+    }
+
+    if let Some(locale) = config.language.clone().or_else(i18n::env_locale) {
+        i18n::select(&locale, &config.locales);
+    }
+
+    let gtk = if matches.opt_present("text") {
+        Err("--text given".to_string())
+    } else {
+        gtk4::init().map_err(|e| e.to_string())
+    };
+    if let Err(reason) = gtk {
+        logging::warning("no graphics, falling back to the text greeter", &[("reason", &reason)]);
+        if let Err(e) = tui::run(&config) {
+            logging::error(&e.to_string(), &[]);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let config = Rc::new(config);
+
     let app = Application::builder()
         .application_id("ekah.scu.octobacillus")
         .build();
 
-    app.connect_activate(|app| {
-        build_ui(app);
-        
-        thread::spawn(|| {
-            let (_stream, stream_handle) = OutputStream::try_default()
-                .expect("Could not find audio device");
-            
-            let sink = Sink::try_new(&stream_handle).expect("Could not create sink");
-
-
-            if let Some(sound_path) = get_startup_sound_path("/var/lib/cynager/info.probe") {
-                if let Ok(file) = File::open(sound_path) {
-                    if let Ok(source) = Decoder::new(BufReader::new(file)) {
-                        sink.append(source);
-                        sink.sleep_until_end();
-                    }
-                } else {
-                    eprintln!("Could not find music.mp3");
-                }
-            }
-
-        });
+    app.connect_activate(move |app| {
+        let mut sound = config.sound.clone();
+        if sound.startup.file.is_none() {
+            sound.startup.file = probe_startup_sound(&config);
+        }
+        let player = Player::new(sound);
+        build_ui(app, &config, &player);
+        player.play(sound::Event::Startup);
     });
-    app.run();
+    // Our options are not GTK's, keep them away from GApplication's parser.
+    app.run_with_args(&[program]);
+}
+
+fn use_layer_shell(config: &Config) -> bool {
+    // In demo mode the greeter is a normal window next to the theme being edited.
+    if config.demo.enabled {
+        return false;
+    }
+    match config.layer_shell {
+        LayerShell::Always => true,
+        LayerShell::Never => false,
+        LayerShell::Auto => gtk4_layer_shell::is_supported(),
+    }
 }
 
-fn build_ui(app: &Application) {
+/// A window covering `monitor`, on a layer surface when possible and fullscreen otherwise.
+/// Only the login form's window (`interactive`) takes the keyboard.
+fn new_window(app: &Application, config: &Config, monitor: &gdk::Monitor, interactive: bool) -> ApplicationWindow {
     let window = ApplicationWindow::builder()
         .application(app)
         .title("octobacillus")
@@ -168,11 +448,32 @@ fn build_ui(app: &Application) {
         .default_height(1080)
         .build();
 
-    // window.init_layer_shell();
-    // window.set_layer(Layer::Overlay);
-    window.auto_exclusive_zone_enable();
-    window.fullscreen();
-    window.set_decorated(false);
+    if config.demo.enabled {
+        return window;
+    }
+
+    if !use_layer_shell(config) {
+        window.fullscreen_on_monitor(monitor);
+        window.set_decorated(false);
+        return window;
+    }
+
+    window.init_layer_shell();
+    window.set_layer(match config.layer {
+        config::Layer::Background => Layer::Background,
+        config::Layer::Bottom => Layer::Bottom,
+        config::Layer::Top => Layer::Top,
+        config::Layer::Overlay => Layer::Overlay,
+    });
+    window.set_keyboard_mode(match config.keyboard_interactivity {
+        _ if !interactive => KeyboardMode::None,
+        KeyboardInteractivity::None => KeyboardMode::None,
+        KeyboardInteractivity::Exclusive => KeyboardMode::Exclusive,
+        KeyboardInteractivity::OnDemand => KeyboardMode::OnDemand,
+    });
+    window.set_monitor(Some(monitor));
+    // Cover panels instead of being pushed aside by their exclusive zones.
+    window.set_exclusive_zone(-1);
     window.set_namespace(Some("octobacillus_l"));
 
     for (edge, anchor) in [
@@ -184,129 +485,170 @@ fn build_ui(app: &Application) {
         window.set_anchor(edge, anchor);
     }
 
-    let css = CssProvider::new();
-    css.load_from_data(
-        "
-        #time {
-            font-family: Cantarell;
-            font-size: 106px;
-            letter-spacing: -2px;
-            font-weight: 900;
-            color: rgba(255, 255, 255, 0.5);
-        }
+    window
+}
 
-        #user {
-            font-family: Cantarell;
-            font-size: 15px;
-            font-weight: 900;
-            color: rgba(255, 255, 255, 0.5);
-        }
+fn clock(config: &Config) -> Label {
+    let time = Label::new(Some("cynageOS"));
+    time.set_widget_name("time");
+    time.set_margin_top(100);
+    let label_weak = time.downgrade();
+    let mut prev = String::from("cynageOS");
+    let clock_format = config.clock_format.clone();
+    let typing_delay = config.typing_delay;
 
-        #boxxy {
-            --color: #72727211;
-  background-color: #f3f3f300;
-  background-image: linear-gradient(0deg, transparent 24%, var(--color) 25%, var(--color) 26%, transparent 27%,transparent 74%, var(--color) 75%, var(--color) 76%, transparent 77%,transparent),
-      linear-gradient(90deg, transparent 24%, var(--color) 25%, var(--color) 26%, transparent 27%,transparent 74%, var(--color) 75%, var(--color) 76%, transparent 77%,transparent);
-  background-size: 55px 55px;
-        }
+    glib::timeout_add_seconds_local(1, move || {
+        if let Some(label) = label_weak.upgrade() {
+            if idle::blanked() {
+                return glib::ControlFlow::Continue;
+            }
+            let now = Local::now();
+            let current = now.format(&i18n::localize(&now, &clock_format)).to_string();
 
-        .password {
-            all: unset;
-            padding: 10px;
-            background-color: rgba(255, 255, 255, 0.32);
-            border-radius: 50px;
-            border: 1px solid rgba(255, 255, 255, 0.18);
-            color: white;
-            caret-color: white;
-        }
+            if prev != current {
+                prev = current.clone();
+                typing_effect(&label, &current, typing_delay);
+            }
 
-        .shake-error {
-            animation: shake 0.4s ease-in-out;
+            glib::ControlFlow::Continue
+        } else {
+            glib::ControlFlow::Break
         }
+    });
+    time
+}
 
-        @keyframes shake {
-            0%, 100% { transform: translateX(0); }
-            20% { transform: translateX(-10px); }
-            40% { transform: translateX(10px); }
-            60% { transform: translateX(-10px); }
-            80% { transform: translateX(10px); }
-        }
+fn monitor_list(display: &gdk::Display) -> Vec<gdk::Monitor> {
+    let monitors = display.monitors();
+    (0..monitors.n_items())
+        .filter_map(|i| monitors.item(i).and_downcast::<gdk::Monitor>())
+        .collect()
+}
 
-        .calendar-container {
-            background-color:rgba(255, 255, 255, 0);
-            border-radius: 50px;
-            padding: 12px;
-            border: 1px solid rgba(255, 255, 255, 0);
-        }
-        .day-label {
-            all: unset;
-            background-color: transparent;
-            color: white;
-            border: none;
-            font-weight: 500;
-            border-radius: 12px;
-            padding: 2px;
-            padding-right: 10px;
-            padding-left: 10px;
-            margin-right: 15px;
-            margin-left: 15px;
-        }
-        .date-button {
-            all: unset;
-            background-color: transparent;
-            color: white;
-            border: none;
-            font-weight: 500;
-            border-radius: 12px;
-            padding: 2px;
-            padding-right: 10px;
-            padding-left: 10px;
-            margin-right: 20px;
-            margin-left: 20px;
-        }
-        .date-button.today {
-            background-color: rgba(255, 255, 255, 0.2);
-            color: black;
-            font-weight: bold;
-        }
+/// Whether both are the same output. Plugged in again, an output is a new
+/// `Monitor`, but keeps its connector.
+fn same_output(a: &gdk::Monitor, b: &gdk::Monitor) -> bool {
+    match (a.connector(), b.connector()) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+    }
+}
 
-        label {
-            transition: margin 0.1s ease-in-out;
-        }
+/// Where the login form goes: `[display] primary` if it is connected, else the
+/// output it is on already, else the first one.
+fn pick_primary(config: &Config, monitors: &[gdk::Monitor], current: Option<&gdk::Monitor>) -> Option<gdk::Monitor> {
+    let configured = config
+        .primary_monitor
+        .as_ref()
+        .and_then(|name| monitors.iter().find(|m| m.connector().is_some_and(|c| c == *name)));
+    let current = current.and_then(|current| monitors.iter().find(|m| same_output(m, current)));
+    configured.or(current).or(monitors.first()).cloned()
+}
 
-        #status{
-            font-size: 12px;
-            font-weight: 900;
-            color: rgba(255, 83, 83, 1);
-        }
+/// Background and clock only, for the outputs the login form is not on.
+fn build_mirror(
+    app: &Application,
+    config: &Config,
+    monitor: &gdk::Monitor,
+    greeter: &ApplicationWindow,
+) -> ApplicationWindow {
+    let window = new_window(app, config, monitor, false);
+    {
+        // Without layer shell a click makes it the active toplevel, the login form
+        // would lose the keyboard.
+        let greeter = greeter.downgrade();
+        window.connect_is_active_notify(move |window| {
+            if window.is_active()
+                && let Some(greeter) = greeter.upgrade()
+            {
+                greeter.present();
+            }
+        });
+    }
+
+    let overlay = Overlay::new();
+    overlay.set_widget_name("main");
+    overlay.set_child(Some(&background::widget(config)));
+
+    let boxxy = GtkBox::new(Orientation::Vertical, 10);
+    boxxy.set_widget_name("boxxy");
+    boxxy.append(&clock(config));
+    overlay.add_overlay(&boxxy);
+
+    window.set_child(Some(&overlay));
+    idle::watch(&window, &overlay);
+    window.show();
+    window
+}
+
+fn build_ui(app: &Application, config: &Rc<Config>, player: &Player) {
+    let display = gdk::Display::default().unwrap();
+    if let Some(monitor) = theme::apply(config) {
+        app.connect_shutdown(move |_| {
+            monitor.cancel();
+        });
+    }
+    accessibility::apply(&config.accessibility);
+    idle::start(&config.idle);
 
-        #gif-bg {
-            opacity: 0;
-            animation: fadeInAnimation 2s ease-in-out 1s forwards;
+    let Some(primary) = pick_primary(config, &monitor_list(&display), None) else {
+        logging::error("no monitors", &[]);
+        return;
+    };
+
+    let greeter = build_greeter(app, config, &primary, player);
+
+    if config.demo.enabled {
+        return;
+    }
+
+    // The login form follows `[display] primary` when it is plugged in, and moves
+    // to another output when its own goes away.
+    let primary = RefCell::new(primary);
+    let mirrors: Rc<RefCell<Vec<(gdk::Monitor, ApplicationWindow)>>> = Rc::new(RefCell::new(Vec::new()));
+    let app = app.clone();
+    let config = config.clone();
+    let reconcile = move |display: &gdk::Display| {
+        let monitors = monitor_list(display);
+        let Some(wanted) = pick_primary(&config, &monitors, Some(&primary.borrow())) else {
+            return;
+        };
+        if *primary.borrow() != wanted {
+            logging::info("moving the login form", &[("output", &wanted.connector().unwrap_or_default())]);
+            if use_layer_shell(&config) {
+                greeter.set_monitor(Some(&wanted));
+            } else {
+                greeter.fullscreen_on_monitor(&wanted);
+            }
+            *primary.borrow_mut() = wanted;
+        }
+        if !config.mirror {
+            return;
         }
 
-        @keyframes fadeInAnimation {
-            from {
-                opacity: 0;
+        let primary = primary.borrow();
+        let mut mirrors = mirrors.borrow_mut();
+        mirrors.retain(|(monitor, window)| {
+            let keep = monitors.contains(monitor) && !same_output(monitor, &primary);
+            if !keep {
+                window.destroy();
             }
-            to {
-                opacity: 1;
+            keep
+        });
+        for monitor in monitors {
+            if !same_output(&monitor, &primary) && !mirrors.iter().any(|(m, _)| same_output(m, &monitor)) {
+                let window = build_mirror(&app, &config, &monitor, &greeter);
+                mirrors.push((monitor, window));
             }
         }
+    };
 
+    reconcile(&display);
+    display.monitors().connect_items_changed(move |_, _, _, _| reconcile(&display));
+}
 
-        #main{
-            background-color: black;
-        }
-
-        ",  
-    );
-    
-    gtk4::style_context_add_provider_for_display(
-        &gtk4::gdk::Display::default().unwrap(),
-        &css,
-        gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION,
-    );
+fn build_greeter(app: &Application, config: &Rc<Config>, monitor: &gdk::Monitor, player: &Player) -> ApplicationWindow {
+    let window = new_window(app, config, monitor, true);
 
     let overlay = Overlay::new();
     overlay.set_widget_name("main");
@@ -317,94 +659,34 @@ fn build_ui(app: &Application) {
     boxxy.set_valign(gtk4::Align::Fill);
     boxxy.set_widget_name("boxxy");
 
-    let gif_path = "/usr/share/octobacillus/bg.png";
-    let file = gtk4::gio::File::for_path(gif_path);
-
-    let gif = Picture::for_file(&file);
-    gif.set_widget_name("gif-bg");
-    gif.set_hexpand(true);
-    gif.set_vexpand(true);
-    gif.set_keep_aspect_ratio(false);
-    gif.set_halign(gtk4::Align::Fill);
-    gif.set_valign(gtk4::Align::Fill);
-    
-
-    overlay.set_child(Some(&gif));
+    overlay.set_child(Some(&background::widget(config)));
     
-    let status = Label::new(None);
+    let status = Label::builder().accessible_role(gtk4::AccessibleRole::Status).build();
     status.set_widget_name("status");
     status.set_margin_bottom(10);
     let username_entry = Label::new(None);
     username_entry.set_widget_name("user");
-    let password_entry = Entry::builder().placeholder_text("Enter Password").visibility(false).build();
+    let password_entry = Entry::builder().visibility(false).build();
+    {
+        let entry = password_entry.downgrade();
+        i18n::on_change(move || {
+            if let Some(entry) = entry.upgrade() {
+                entry.set_placeholder_text(Some(&i18n::tr("enter_password")));
+            }
+        });
+    }
     password_entry.add_css_class("password");
+    accessibility::label(&password_entry, "password_prompt");
     gtk4::prelude::EntryExt::set_alignment(&password_entry, 0.5);
     password_entry.set_hexpand(true);
     password_entry.set_vexpand(true);
     password_entry.set_halign(gtk4::Align::Center);
 
-    let time = Label::new(Some("cynageOS"));
-    time.set_widget_name("time");
-    time.set_margin_top(100);
-    let label_weak = time.downgrade();
-    let mut prev = String::from("cynageOS");
-
-    glib::timeout_add_seconds_local(1, move || {
-        if let Some(label) = label_weak.upgrade() {
-            let now = Local::now();
-            let current = now.format("%I:%M %p").to_string();
-
-            if prev != current {
-                prev = current.clone();
-                typing_effect(&label, &current, 50);
-            }
-
-            glib::ControlFlow::Continue
-        } else {
-            glib::ControlFlow::Break
-        }
-    });
+    let time = clock(config);
+    accessibility::description(&time, "clock");
     boxxy.append(&time);
 
-    let container = GtkBox::new(Orientation::Vertical, 8);
-    container.add_css_class("calendar-container");
-
-    let weekdays = ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"];
-    let grid = Grid::new();
-    grid.set_column_spacing(10);
-    grid.set_row_spacing(10);
-    
-    let mut i = 0;
-    for day in weekdays {
-        let label = Label::new(Some(day));
-        label.add_css_class("day-label");
-        grid.attach(&label, i, 0, 1, 1);
-        i+=1;
-    }
-
-    let today = Local::now().date_naive();
-    let start_of_week = today - chrono::Duration::days((today.weekday().num_days_from_monday()) as i64);
-
-    for i in 0..7 {
-        let date = start_of_week + chrono::Duration::days(i);
-        let day_label = Button::with_label(&format!("{}", date.day()));
-        day_label.add_css_class("date-button");
-
-        if date == today {
-            day_label.add_css_class("today");
-        }
-
-        // dates_row.append(&day_label);
-        grid.attach(&day_label, i.try_into().unwrap(), 1, 1, 1);
-    }
-
-    container.append(&grid);
-    // container.append(&dates_row);
-    container.set_hexpand(true);
-    container.set_margin_top(10);
-    container.set_halign(gtk4::Align::Center);
-
-    boxxy.append(&container); // here
+    boxxy.append(&calendar::widget(&config.calendar));
 
     window.set_child(Some(&overlay));
     window.show();
@@ -413,6 +695,7 @@ fn build_ui(app: &Application) {
     workingbox.set_vexpand(true);
     workingbox.set_valign(gtk4::Align::End);
     workingbox.set_widget_name("workin");
+    accessibility::description(&username_entry, "welcome_description");
     workingbox.append(&username_entry);
     boxxy.append(&workingbox);
 
@@ -422,157 +705,303 @@ fn build_ui(app: &Application) {
     workingbox.append(&pass_box);
     workingbox.append(&status);
 
-    let pass_box_weak_key = pass_box.downgrade();
-    let passwork_entry_outer_key = password_entry.clone();
-
-    let key_controller = EventControllerKey::new();
-    key_controller.connect_key_pressed(move |_, _, _, _| {
-        let pass_box_opt = pass_box_weak_key.upgrade();
-        let pass_box_weak_inner = pass_box_opt.clone();
-        let password_entry_clone = passwork_entry_outer_key.clone();
+    if let Some(indicator) = keyboard::lock_indicator(&config.keyboard) {
+        workingbox.append(&indicator);
+    }
 
-        glib::timeout_add_local(std::time::Duration::from_millis(10), move || {
-            if let Some(pass_box) = &pass_box_weak_inner {
-                let current = pass_box.height_request();
-                if current < 30 {
-                    pass_box.set_height_request(current + 1);
-                    pass_box.set_margin_bottom(current + 2);
-                    glib::ControlFlow::Continue
-                } else {
-                    if password_entry_clone.parent().is_none() {
-                        pass_box.append(&password_entry_clone);
-                    }
-                    password_entry_clone.grab_focus();
-                    glib::ControlFlow::Break
-                }
-            } else {
-                glib::ControlFlow::Break
+    // Asked for only when no user is remembered.
+    let user_entry = Entry::new();
+    user_entry.set_widget_name("username");
+    {
+        let entry = user_entry.downgrade();
+        i18n::on_change(move || {
+            if let Some(entry) = entry.upgrade() {
+                entry.set_placeholder_text(Some(&i18n::tr("enter_username")));
             }
         });
-        
-        gtk4::glib::Propagation::Stop
-    });
+    }
+    user_entry.add_css_class("password");
+    accessibility::label(&user_entry, "login_prompt");
+    gtk4::prelude::EntryExt::set_alignment(&user_entry, 0.5);
+    user_entry.set_halign(gtk4::Align::Center);
+    {
+        let password_entry = password_entry.downgrade();
+        user_entry.connect_activate(move |_| {
+            if let Some(entry) = password_entry.upgrade() {
+                entry.grab_focus();
+            }
+        });
+    }
+    workingbox.insert_child_after(&user_entry, Some(&username_entry));
+
+    // The entry is there and focused from the start, so the keyboard and screen
+    // readers can use it right away; with animations it fades in on first use.
+    pass_box.append(&password_entry);
+    let reveal: Rc<dyn Fn()> = if config.accessibility.animations {
+        password_entry.set_opacity(0.0);
+        let pass_box = pass_box.downgrade();
+        let entry = password_entry.downgrade();
+        let revealing = Rc::new(Cell::new(false));
+        Rc::new(move || {
+            if revealing.replace(true) {
+                return;
+            }
+            let pass_box = pass_box.clone();
+            let entry = entry.clone();
+            glib::timeout_add_local(Duration::from_millis(10), move || {
+                let (Some(pass_box), Some(entry)) = (pass_box.upgrade(), entry.upgrade()) else {
+                    return glib::ControlFlow::Break;
+                };
+                let current = pass_box.height_request();
+                if current >= 30 {
+                    entry.set_opacity(1.0);
+                    return glib::ControlFlow::Break;
+                }
+                pass_box.set_height_request(current + 1);
+                pass_box.set_margin_bottom(current + 2);
+                entry.set_opacity(f64::from(current - 4) / 26.0);
+                glib::ControlFlow::Continue
+            });
+        })
+    } else {
+        Rc::new(|| {})
+    };
 
+    // In the capture phase, so the key still reaches the entry.
+    let key_controller = EventControllerKey::new();
+    key_controller.set_propagation_phase(gtk4::PropagationPhase::Capture);
+    let reveal_key = reveal.clone();
+    key_controller.connect_key_pressed(move |_, _, _, _| {
+        reveal_key();
+        glib::Propagation::Proceed
+    });
     window.add_controller(key_controller);
-    
-    let pass_box_weak = pass_box.downgrade();
-    // Add motion controller to workingbox
-    let motion_controller = EventControllerMotion::new();
-    workingbox.add_controller(motion_controller.clone());
-    let passwork_entry_outer = password_entry.clone();
 
-    motion_controller.connect_enter(move |_, _, _| {
-        let pass_box_weak_inner = pass_box_weak.clone();
-        let password_entry_clone = passwork_entry_outer.clone();
+    let motion_controller = EventControllerMotion::new();
+    let reveal_motion = reveal.clone();
+    motion_controller.connect_enter(move |_, _, _| reveal_motion());
+    workingbox.add_controller(motion_controller);
+
+    let last_user = read_username_from_file(&config.user_file);
+    make_label_bouncy(&username_entry, config.bounce_amplitude, config.bounce_speed);
+    if let Some(u) = last_user {
+        let label = username_entry.downgrade();
+        i18n::on_change(move || {
+            if let Some(label) = label.upgrade() {
+                label.set_text(&i18n::trf("welcome", &[("user", &u)]));
+            }
+        });
+        username_entry.set_visible(true);
+        user_entry.set_visible(false);
+        password_entry.grab_focus();
+    } else {
+        username_entry.set_visible(false);
+        user_entry.grab_focus();
+    }
 
-        glib::timeout_add_local(std::time::Duration::from_millis(10), move || {
-            if let Some(pass_box) = pass_box_weak_inner.upgrade() {
-                let current = pass_box.height_request();
-                if current < 30 {
-                    pass_box.set_height_request(current + 1);
-                    pass_box.set_margin_bottom(current + 2);
-                    glib::ControlFlow::Continue
-                } else {
-                    pass_box.append(&password_entry_clone);
-                    password_entry_clone.grab_focus();
-                    glib::ControlFlow::Break
-                }
-            } else {
-                glib::ControlFlow::Break
+    let cancel_button = Button::new();
+    {
+        let button = cancel_button.downgrade();
+        i18n::on_change(move || {
+            if let Some(button) = button.upgrade() {
+                button.set_label(&i18n::tr("cancel"));
             }
         });
-    });
+    }
+    cancel_button.add_css_class("cancel");
+    cancel_button.set_halign(gtk4::Align::Center);
+    cancel_button.set_visible(false);
+    workingbox.append(&cancel_button);
+
+    let targets = [user_entry.clone(), password_entry.clone()];
+    let reveal_osk = reveal.clone();
+    let on_screen = osk::widget(&config.osk, &targets, move || reveal_osk());
+    if let Some(keyboard) = &on_screen {
+        workingbox.append(keyboard);
+        if config.osk.auto_show {
+            // Touching the screen is the one sign there may be no keyboard.
+            let touch = gtk4::EventControllerLegacy::new();
+            touch.set_propagation_phase(gtk4::PropagationPhase::Capture);
+            let keyboard = keyboard.downgrade();
+            touch.connect_event(move |_, event| {
+                if event.event_type() == gdk::EventType::TouchBegin
+                    && let Some(keyboard) = keyboard.upgrade()
+                {
+                    keyboard.set_visible(true);
+                    reveal();
+                }
+                glib::Propagation::Proceed
+            });
+            window.add_controller(touch);
+        }
+    }
 
-    let last_user = read_username_from_file();
-    make_label_bouncy(&username_entry, 10.0, 0.7);
-    if let Some(u) = &last_user {
-        username_entry.set_text(&format!("welcome, {}", u));
-        username_entry.set_visible(true);
+    let mut locked: Vec<gtk4::Widget> = vec![user_entry.clone().upcast(), password_entry.clone().upcast()];
+    locked.extend(on_screen.iter().map(|keyboard| keyboard.clone().upcast()));
+    banner::show(&config.banner, &workingbox, &locked);
+
+    let top_bar = GtkBox::new(Orientation::Horizontal, 10);
+    top_bar.set_widget_name("top-bar");
+    top_bar.set_halign(gtk4::Align::End);
+    top_bar.set_valign(gtk4::Align::Start);
+    top_bar.set_margin_top(20);
+    top_bar.set_margin_end(20);
+    let language = Rc::new(RefCell::new(None));
+    let layout = Rc::new(RefCell::new(None));
+    if let Some(toggles) = accessibility::toggles(&config.accessibility) {
+        top_bar.append(&toggles);
+    }
+    if let Some(keyboard) = &on_screen {
+        top_bar.append(&osk::toggle(keyboard));
+    }
+    if let Some(picker) = language_picker(config, &language) {
+        accessibility::description(&picker, "language");
+        top_bar.append(&picker);
+    }
+    if let Some(switcher) = keyboard::layout_switcher(&config.keyboard, &layout) {
+        accessibility::description(&switcher, "keyboard_layout");
+        top_bar.append(&switcher);
     }
+    if config.sound.mute_button && config.sound.enabled {
+        top_bar.append(&mute_button(player));
+    }
+    if let Some(power_menu) = power::menu(&config.power, &status) {
+        accessibility::label(&power_menu, "power_menu");
+        top_bar.append(&power_menu);
+    }
+    overlay.add_overlay(&top_bar);
+    if let Some(bar) = statusbar::widget(&config.status_bar) {
+        bar.set_halign(gtk4::Align::Start);
+        bar.set_valign(gtk4::Align::Start);
+        bar.set_margin_top(20);
+        bar.set_margin_start(20);
+        overlay.add_overlay(&bar);
+    }
+    // Last, so the shade covers everything else.
+    idle::watch(&window, &overlay);
 
     let status = Rc::new(status);
-    let username_entry_rc = Rc::new(username_entry);
     let password_entry_rc = Rc::new(password_entry.clone());
-    let window_rc = Rc::new(window);
-    let attempty = Rc::new(Cell::new(3));
+    let cancel_button_rc = Rc::new(cancel_button.clone());
+    let window_rc = Rc::new(window.clone());
+    let flow = Arc::new(Mutex::new(AuthFlow::new(Lockout::new(
+        config.max_attempts,
+        config.lockout_base,
+        config.lockout_max,
+    ))));
+
+    let flow_cancel = flow.clone();
+    let status_cancel = status.clone();
+    cancel_button.connect_clicked(move |_| {
+        let mut flow = flow_cancel.lock().unwrap();
+        if flow.busy() {
+            flow.cancel();
+            status_cancel.set_text(&i18n::tr("cancelling"));
+        }
+    });
 
+    let config = config.clone();
+    let player = player.clone();
     password_entry.connect_activate(move |_entry| {
-        let username_entry = username_entry_rc.clone();
         let password_entry = password_entry_rc.clone();
+        let player = player.clone();
+        let cancel_button = cancel_button_rc.clone();
         let status = status.clone();
         let window = window_rc.clone();
+        let fade_duration = config.fade_duration;
+        let flow = flow.clone();
 
-        let username = if let Some(user) = read_username_from_file() {
-            user
-        } else {
-            username_entry.text().to_string()
+        let Some(backend) = ipc::backend(&config) else {
+            status.set_text(&i18n::tr("no_socket"));
+            return;
         };
-        let password = password_entry.text().to_string();
 
-        let mut stream = match UnixStream::connect(env::var("GREETD_SOCK").unwrap()) {
-            Ok(s) => s,
-            Err(e) => {
-                status.set_text(&format!("Connection error: {e}"));
+        let username = if WidgetExt::is_visible(&user_entry) {
+            user_entry.text().to_string()
+        } else {
+            read_username_from_file(&config.user_file).unwrap_or_default()
+        };
+        let choices = Choices {
+            locale: language.borrow().clone().or_else(|| config.language.clone()).or_else(i18n::env_locale),
+            layout: layout.borrow().clone(),
+        };
+        let mut env = config.session_env(&config.command, &choices);
+        if let Some(lang) = &*language.borrow() {
+            env.retain(|e| !e.starts_with("LANG="));
+            env.push(format!("LANG={lang}"));
+        }
+        let creds = Credentials {
+            username,
+            password: password_entry.text().to_string(),
+            env,
+            cmd: config.command.clone(),
+        };
+        let login = flow.lock().unwrap().login(creds, Instant::now());
+        let first = match login {
+            Ok(request) => request,
+            Err(Refused::Busy) => return,
+            Err(Refused::LockedOut(_)) => {
+                show_lockout(&flow, &password_entry, &status);
                 return;
             }
         };
+        let events = ipc::spawn(backend, flow.clone(), first);
+
+        status.set_text(&i18n::tr("authenticating"));
+        password_entry.set_sensitive(false);
+        cancel_button.set_visible(true);
+
+        glib::timeout_add_local(std::time::Duration::from_millis(50), move || {
+            let event = match events.try_recv() {
+                Ok(event) => event,
+                Err(mpsc::TryRecvError::Empty) => {
+                    // Past this point greetd is starting the session, nothing left to cancel.
+                    if cancel_button.is_visible() && flow.lock().unwrap().state() == State::Starting {
+                        cancel_button.set_visible(false);
+                        status.set_text(&i18n::tr("starting_session"));
+                    }
+                    return glib::ControlFlow::Continue;
+                }
+                // The worker is done and every event was handled.
+                Err(mpsc::TryRecvError::Disconnected) => return glib::ControlFlow::Break,
+            };
 
-        let mut next_request = Request::CreateSession { username: username.clone() };
-        let mut starting = false;
-        let attempty = attempty.clone();
-        
-        loop {
-            if let Err(e) = next_request.write_to(&mut stream) {
-                status.set_text(&format!("Write error: {e}"));
-                break;
+            if let UiEvent::Message(text) = &event {
+                status.set_text(text);
+                return glib::ControlFlow::Continue;
             }
-            match Response::read_from(&mut stream) {
-                Ok(Response::AuthMessage { auth_message: _, auth_message_type }) => {
-                    let response = match auth_message_type {
-                        AuthMessageType::Visible => Some(username.clone()),
-                        AuthMessageType::Secret => Some(password.clone()),
-                        _ => None,
-                    };
-                    next_request = Request::PostAuthMessageResponse { response };
+
+            password_entry.set_sensitive(true);
+            cancel_button.set_visible(false);
+
+            match event {
+                UiEvent::Started => {
+                    player.play(sound::Event::LoginSuccess);
+                    status.set_text("");
+                    fade_out_and_quit(&window, fade_duration, &player);
                 }
-                Ok(Response::Success) => {
-                    if starting {
-                        fade_out_and_quit(&window);
-                        break;
-                    } else {
-                        starting = true;
-                        next_request = Request::StartSession {
-                            env: vec![format!("NIRI_CONFIG=/var/lib/cynager/niri/config.kdl")],
-                            cmd: vec!["niri-session".to_string()],
-                        };
-                    }
+                UiEvent::WrongPassword { attempts_left } => {
+                    player.play(sound::Event::AuthFailure);
+                    shake(&password_entry);
+                    status.set_text(&UiEvent::WrongPassword { attempts_left }.describe());
                 }
-                Ok(Response::Error { description, .. }) => {
-                    let attemptyc = attempty.get();
-                    let new = attemptyc - 1;
-                    attempty.set(new);
-
-                    password_entry.add_css_class("shake-error");
-                    status.set_text(&format!("Da Password is Wrong, attemps left: {}", new));
-                    password_entry.set_text("");
-                    glib::timeout_add_local(std::time::Duration::from_millis(400), {
-                        let entry_weak = password_entry.downgrade();
-                        move || {
-                            if let Some(entry) = entry_weak.upgrade() {
-                                entry.remove_css_class("shake-error");
-                            }
-                            glib::ControlFlow::Break
-                        }
-                    });
-                    let _ = Request::CancelSession.write_to(&mut stream);
-                    break;
+                UiEvent::LockedOut(delay) => {
+                    player.play(sound::Event::Lockout);
+                    shake(&password_entry);
+                    status.set_text(&UiEvent::LockedOut(delay).describe());
+                    show_lockout(&flow, &password_entry, &status);
                 }
-                Err(e) => {
-                    status.set_text(&format!("Response error: {e}"));
-                    break;
+                UiEvent::Error(text) => status.set_text(&text),
+                UiEvent::Cancelled => {
+                    status.set_text("");
+                    password_entry.set_text("");
                 }
+                UiEvent::Message(_) => unreachable!(),
             }
-        }
+            password_entry.grab_focus();
+            glib::ControlFlow::Continue
+        });
     });
 
+    window
 }
@@ -0,0 +1,239 @@
+use crate::i18n;
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Button, Entry, Orientation, accessible::Property};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+/// One keyboard layout: rows of keys, each key typing one character.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layout {
+    pub name: String,
+    pub rows: Vec<Vec<char>>,
+}
+
+/// `[osk]` settings.
+#[derive(Clone, Debug)]
+pub struct Osk {
+    /// `[osk] enabled`, offers the on-screen keyboard at all
+    pub enabled: bool,
+    /// `[osk] auto_show`, shows it as soon as the screen is touched
+    pub auto_show: bool,
+    /// `[osk] layouts`, switched between with the layout key
+    pub layouts: Vec<Layout>,
+}
+
+impl Default for Osk {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            auto_show: true,
+            layouts: ["qwerty", "symbols"]
+                .into_iter()
+                .filter_map(builtin)
+                .collect(),
+        }
+    }
+}
+
+/// The layouts `[osk] layouts` can name without defining them in `[osk_layouts]`.
+const BUILTIN: [(&str, &str); 4] = [
+    ("qwerty", "1234567890 qwertyuiop asdfghjkl zxcvbnm"),
+    ("qwertz", "1234567890 qwertzuiopü asdfghjklöä yxcvbnm"),
+    ("azerty", "1234567890 azertyuiop qsdfghjklm wxcvbn"),
+    ("symbols", "!@#$%^&*() -_=+[]{}\\| ;:'\",./<>? `~"),
+];
+
+/// A layout from its rows, separated by whitespace. Every character is a key.
+pub fn layout(name: &str, rows: &str) -> Layout {
+    Layout {
+        name: name.to_string(),
+        rows: rows.split_whitespace().map(|row| row.chars().collect()).collect(),
+    }
+}
+
+pub fn builtin(name: &str) -> Option<Layout> {
+    BUILTIN
+        .iter()
+        .find(|(builtin, _)| *builtin == name)
+        .map(|(name, rows)| layout(name, rows))
+}
+
+/// A key that does not stay focused, so the entry keeps the caret.
+fn key(label: &str) -> Button {
+    let button = Button::with_label(label);
+    button.add_css_class("osk-key");
+    button.set_focus_on_click(false);
+    button.set_focusable(false);
+    button
+}
+
+fn set_label(button: &Button, label: &str, id: &'static str) {
+    button.set_label(label);
+    let button = button.downgrade();
+    i18n::on_change(move || {
+        if let Some(button) = button.upgrade() {
+            button.update_property(&[Property::Label(&i18n::tr(id))]);
+        }
+    });
+}
+
+/// The keyboard, typing into whichever entry of `targets` had the focus last.
+/// `on_use` runs on every key press.
+pub fn widget(osk: &Osk, targets: &[Entry], on_use: impl Fn() + 'static) -> Option<GtkBox> {
+    if !osk.enabled || osk.layouts.is_empty() {
+        return None;
+    }
+
+    let target = Rc::new(RefCell::new(targets.last()?.clone()));
+    for entry in targets {
+        let focus = gtk4::EventControllerFocus::new();
+        let target = target.clone();
+        let entry_ref = entry.clone();
+        focus.connect_enter(move |_| *target.borrow_mut() = entry_ref.clone());
+        entry.add_controller(focus);
+    }
+
+    let keyboard = GtkBox::new(Orientation::Vertical, 4);
+    keyboard.set_widget_name("osk");
+    keyboard.set_halign(gtk4::Align::Center);
+    keyboard.set_visible(false);
+
+    let rows = GtkBox::new(Orientation::Vertical, 4);
+    keyboard.append(&rows);
+
+    let on_use = Rc::new(on_use);
+    let type_text = {
+        let target = target.clone();
+        let on_use = on_use.clone();
+        move |text: &str| {
+            on_use();
+            let entry = target.borrow();
+            let mut position = entry.position();
+            entry.insert_text(text, &mut position);
+            entry.set_position(position);
+        }
+    };
+
+    let layouts = Rc::new(osk.layouts.clone());
+    let current = Rc::new(Cell::new(0));
+    let shift = Rc::new(Cell::new(false));
+
+    // Rebuilds the character rows for the current layout and shift state.
+    let fill: Rc<dyn Fn()> = {
+        let layouts = layouts.clone();
+        let current = current.clone();
+        let shift = shift.clone();
+        let rows = rows.clone();
+        let type_text = Rc::new(type_text.clone());
+        Rc::new(move || {
+            while let Some(child) = rows.first_child() {
+                rows.remove(&child);
+            }
+            for row in &layouts[current.get()].rows {
+                let line = GtkBox::new(Orientation::Horizontal, 4);
+                line.set_halign(gtk4::Align::Center);
+                for c in row {
+                    let text: String = if shift.get() { c.to_uppercase().collect() } else { c.to_string() };
+                    let button = key(&text);
+                    let type_text = type_text.clone();
+                    button.connect_clicked(move |_| type_text(&text));
+                    line.append(&button);
+                }
+                rows.append(&line);
+            }
+        })
+    };
+    fill();
+
+    let bottom = GtkBox::new(Orientation::Horizontal, 4);
+    bottom.set_halign(gtk4::Align::Center);
+
+    let shift_key = key("⇧");
+    shift_key.add_css_class("osk-shift");
+    set_label(&shift_key, "⇧", "osk_shift");
+    {
+        let shift = shift.clone();
+        let fill = fill.clone();
+        shift_key.connect_clicked(move |button| {
+            shift.set(!shift.get());
+            if shift.get() {
+                button.add_css_class("active");
+            } else {
+                button.remove_css_class("active");
+            }
+            fill();
+        });
+    }
+    bottom.append(&shift_key);
+
+    if layouts.len() > 1 {
+        let layout_key = key(&layouts[0].name);
+        layout_key.add_css_class("osk-layout");
+        let current = current.clone();
+        let layouts = layouts.clone();
+        let fill = fill.clone();
+        layout_key.connect_clicked(move |button| {
+            current.set((current.get() + 1) % layouts.len());
+            button.set_label(&layouts[current.get()].name);
+            fill();
+        });
+        bottom.append(&layout_key);
+    }
+
+    let space = key(" ");
+    space.add_css_class("osk-space");
+    set_label(&space, " ", "osk_space");
+    {
+        let type_text = type_text.clone();
+        space.connect_clicked(move |_| type_text(" "));
+    }
+    bottom.append(&space);
+
+    let backspace = key("⌫");
+    set_label(&backspace, "⌫", "osk_backspace");
+    {
+        let target = target.clone();
+        let on_use = on_use.clone();
+        backspace.connect_clicked(move |_| {
+            on_use();
+            let entry = target.borrow();
+            let position = entry.position();
+            if position > 0 {
+                entry.delete_text(position - 1, position);
+            }
+        });
+    }
+    bottom.append(&backspace);
+
+    let enter = key("⏎");
+    set_label(&enter, "⏎", "osk_enter");
+    enter.connect_clicked(move |_| {
+        on_use();
+        target.borrow().emit_activate();
+    });
+    bottom.append(&enter);
+
+    keyboard.append(&bottom);
+    Some(keyboard)
+}
+
+/// The top bar button showing and hiding `keyboard`.
+pub fn toggle(keyboard: &GtkBox) -> Button {
+    let button = Button::with_label("⌨");
+    button.set_widget_name("osk-toggle");
+    {
+        let button = button.downgrade();
+        i18n::on_change(move || {
+            if let Some(button) = button.upgrade() {
+                let name = i18n::tr("osk");
+                button.set_tooltip_text(Some(&name));
+                button.update_property(&[Property::Label(&name)]);
+            }
+        });
+    }
+    let keyboard = keyboard.clone();
+    button.connect_clicked(move |_| keyboard.set_visible(!keyboard.is_visible()));
+    button
+}
@@ -0,0 +1,238 @@
+use crate::{i18n, logging, process};
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Button, Label, MenuButton, Orientation, Popover, Stack, gio, glib};
+use std::{cell::Cell, rc::Rc};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    PowerOff,
+    Reboot,
+    Suspend,
+    Hibernate,
+}
+
+impl Action {
+    pub const ALL: [Action; 4] = [Action::PowerOff, Action::Reboot, Action::Suspend, Action::Hibernate];
+
+    /// The logind method, `Can` prefixed for the availability check.
+    fn method(self) -> &'static str {
+        match self {
+            Action::PowerOff => "PowerOff",
+            Action::Reboot => "Reboot",
+            Action::Suspend => "Suspend",
+            Action::Hibernate => "Hibernate",
+        }
+    }
+
+    fn label(self) -> String {
+        i18n::tr(match self {
+            Action::PowerOff => "power_off",
+            Action::Reboot => "reboot",
+            Action::Suspend => "suspend",
+            Action::Hibernate => "hibernate",
+        })
+    }
+}
+
+/// `[power]` settings.
+#[derive(Clone, Debug)]
+pub struct Power {
+    /// `[power] enabled`, shows the power menu at all
+    pub enabled: bool,
+    /// `[power] confirm`, asks before acting
+    pub confirm: bool,
+    /// `[power] poweroff`, `reboot`, `suspend`, `hibernate`: commands used instead of logind
+    pub commands: Vec<(Action, Vec<String>)>,
+}
+
+impl Default for Power {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            confirm: true,
+            commands: Vec::new(),
+        }
+    }
+}
+
+impl Power {
+    fn command(&self, action: Action) -> Option<&[String]> {
+        self.commands
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, cmd)| cmd.as_slice())
+    }
+
+    /// Whether the action can be offered: configured, or allowed by logind without
+    /// asking, as there is nobody to answer a polkit `challenge`.
+    fn available(&self, action: Action) -> bool {
+        if self.command(action).is_some() {
+            return true;
+        }
+        // Asked while the greeter is being built, a hung logind must not hold it up.
+        let can = logind_call(
+            &format!("Can{}", action.method()),
+            Some(glib::VariantTy::new("(s)").unwrap()),
+            CAN_TIMEOUT_MS,
+        );
+        match can.as_ref().map(|v| v.child_value(0).str().map(str::to_string)) {
+            Ok(Some(answer)) => answer == "yes",
+            _ => false,
+        }
+    }
+
+    /// Runs `action`, calling `done` with the outcome. logind is asked without
+    /// blocking, a slow one must not freeze the greeter.
+    fn run(&self, action: Action, done: impl FnOnce(Result<(), String>) + 'static) {
+        if let Some(cmd) = self.command(action) {
+            return done(process::spawn_detached(cmd).map_err(|e| format!("{}: {e}", cmd[0])));
+        }
+        // The argument is logind's `interactive`, there is nobody to ask for polkit credentials.
+        let method = action.method();
+        gio::bus_get(gio::BusType::System, gio::Cancellable::NONE, move |bus| {
+            let bus = match bus {
+                Ok(bus) => bus,
+                Err(e) => return done(Err(e.to_string())),
+            };
+            bus.call(
+                Some("org.freedesktop.login1"),
+                "/org/freedesktop/login1",
+                "org.freedesktop.login1.Manager",
+                method,
+                Some(&(false,).to_variant()),
+                None,
+                gio::DBusCallFlags::NONE,
+                -1,
+                gio::Cancellable::NONE,
+                move |reply| done(reply.map(drop).map_err(|e| e.to_string())),
+            );
+        });
+    }
+}
+
+/// How long the `Can*` checks wait for logind, in milliseconds.
+const CAN_TIMEOUT_MS: i32 = 500;
+
+fn logind_call(
+    method: &str,
+    reply_type: Option<&glib::VariantTy>,
+    timeout_ms: i32,
+) -> Result<glib::Variant, glib::Error> {
+    let bus = gio::bus_get_sync(gio::BusType::System, gio::Cancellable::NONE)?;
+    bus.call_sync(
+        Some("org.freedesktop.login1"),
+        "/org/freedesktop/login1",
+        "org.freedesktop.login1.Manager",
+        method,
+        None,
+        reply_type,
+        gio::DBusCallFlags::NONE,
+        timeout_ms,
+        gio::Cancellable::NONE,
+    )
+}
+
+/// The power button and its menu. `None` if disabled or nothing is available.
+pub fn menu(power: &Power, status: &Label) -> Option<MenuButton> {
+    if !power.enabled {
+        return None;
+    }
+    let actions: Vec<Action> = Action::ALL.into_iter().filter(|a| power.available(*a)).collect();
+    if actions.is_empty() {
+        return None;
+    }
+
+    let stack = Stack::new();
+    let popover = Popover::new();
+    popover.add_css_class("power-menu");
+    popover.set_child(Some(&stack));
+
+    let list = GtkBox::new(Orientation::Vertical, 4);
+    stack.add_named(&list, Some("actions"));
+
+    let confirm_box = GtkBox::new(Orientation::Vertical, 8);
+    let question = Label::new(None);
+    let yes = Button::new();
+    let no = Button::new();
+    {
+        let (yes, no) = (yes.downgrade(), no.downgrade());
+        i18n::on_change(move || {
+            if let (Some(yes), Some(no)) = (yes.upgrade(), no.upgrade()) {
+                yes.set_label(&i18n::tr("yes"));
+                no.set_label(&i18n::tr("no"));
+            }
+        });
+    }
+    let answers = GtkBox::new(Orientation::Horizontal, 8);
+    answers.set_halign(gtk4::Align::Center);
+    answers.append(&no);
+    answers.append(&yes);
+    confirm_box.append(&question);
+    confirm_box.append(&answers);
+    stack.add_named(&confirm_box, Some("confirm"));
+
+    let pending = Rc::new(Cell::new(None));
+    let run = {
+        let power = power.clone();
+        let status = status.clone();
+        let popover = popover.clone();
+        move |action: Action| {
+            popover.popdown();
+            logging::info("power action", &[("action", &action.method())]);
+            let status = status.clone();
+            power.run(action, move |result| {
+                if let Err(e) = result {
+                    logging::error("power action failed", &[("action", &action.method()), ("error", &e)]);
+                    status.set_text(&i18n::trf("power_failed", &[("action", &action.label()), ("error", &e)]));
+                }
+            });
+        }
+    };
+
+    for action in actions {
+        let button = Button::new();
+        button.add_css_class("power-action");
+        {
+            let button = button.downgrade();
+            i18n::on_change(move || {
+                if let Some(button) = button.upgrade() {
+                    button.set_label(&action.label());
+                }
+            });
+        }
+        let confirm = power.confirm;
+        let stack = stack.clone();
+        let question = question.clone();
+        let pending = pending.clone();
+        let run = run.clone();
+        button.connect_clicked(move |_| {
+            if confirm {
+                pending.set(Some(action));
+                question.set_text(&i18n::trf("confirm_power", &[("action", &action.label())]));
+                stack.set_visible_child_name("confirm");
+            } else {
+                run(action);
+            }
+        });
+        list.append(&button);
+    }
+
+    yes.connect_clicked(move |_| {
+        if let Some(action) = pending.take() {
+            run(action);
+        }
+    });
+    {
+        let stack = stack.clone();
+        no.connect_clicked(move |_| stack.set_visible_child_name("actions"));
+    }
+    popover.connect_closed(move |_| stack.set_visible_child_name("actions"));
+
+    let button = MenuButton::new();
+    button.set_label("⏻");
+    button.set_widget_name("power");
+    button.set_popover(Some(&popover));
+    button.set_halign(gtk4::Align::End);
+    button.set_valign(gtk4::Align::Start);
+    Some(button)
+}
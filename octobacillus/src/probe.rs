@@ -0,0 +1,101 @@
+use std::{collections::HashMap, error::Error, fs, io, path::Path};
+
+/// Settings from cynager's `info.probe`: `key: value` lines between `:set` and
+/// `:end`. Text outside the blocks is ignored, as are blank lines and `#`
+/// comments inside them. A key set twice keeps its last value.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Probe {
+    entries: HashMap<String, String>,
+}
+
+impl Probe {
+    pub fn parse(s: &str) -> Result<Self, Box<dyn Error>> {
+        let mut entries = HashMap::new();
+        let mut block_start = None;
+
+        for (n, line) in s.lines().enumerate() {
+            let n = n + 1;
+            let line = line.trim();
+            match (line, block_start) {
+                (":set", None) => block_start = Some(n),
+                (":set", Some(start)) => {
+                    return Err(format!("line {n}: :set inside the block opened on line {start}").into());
+                }
+                (":end", Some(_)) => block_start = None,
+                (":end", None) => return Err(format!("line {n}: :end without :set").into()),
+                (_, None) => {}
+                (_, Some(_)) if line.is_empty() || line.starts_with('#') => {}
+                (_, Some(_)) => {
+                    // Only the first `:` separates, values like paths may contain more.
+                    let Some((key, value)) = line.split_once(':') else {
+                        return Err(format!("line {n}: expected `key: value`, got `{line}`").into());
+                    };
+                    let key = key.trim();
+                    if key.is_empty() {
+                        return Err(format!("line {n}: missing key").into());
+                    }
+                    entries.insert(key.to_string(), value.trim().to_string());
+                }
+            }
+        }
+
+        if let Some(start) = block_start {
+            return Err(format!("line {start}: :set is never closed with :end").into());
+        }
+        Ok(Self { entries })
+    }
+
+    /// Reads the probe, empty if the file does not exist.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        match fs::read_to_string(path) {
+            Ok(s) => Self::parse(&s).map_err(|e| format!("{}: {e}", path.display()).into()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("{}: {e}", path.display()).into()),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.get(key).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks() {
+        let probe = Probe::parse(
+            "
+cynager probe, not part of any block
+:set
+# the sound played by the greeter
+startup: boot: remix.mp3
+theme : midnight
+:end
+
+:set
+theme: dawn
+
+wallpaper:
+:end
+",
+        )
+        .expect("probe didn't parse");
+
+        assert_eq!(probe.get("startup"), Some("boot: remix.mp3"));
+        assert_eq!(probe.get("theme"), Some("dawn"));
+        assert_eq!(probe.get("wallpaper"), Some(""));
+        assert_eq!(probe.get("cynager probe, not part of any block"), None);
+    }
+
+    #[test]
+    fn errors() {
+        let error = |s| Probe::parse(s).unwrap_err().to_string();
+        assert_eq!(error(":set\nstartup\n:end"), "line 2: expected `key: value`, got `startup`");
+        assert_eq!(error(":set\n: value\n:end"), "line 2: missing key");
+        assert_eq!(error("\n:set\n:set"), "line 3: :set inside the block opened on line 2");
+        assert_eq!(error(":end"), "line 1: :end without :set");
+        assert_eq!(error("x\n:set\nkey: value"), "line 2: :set is never closed with :end");
+    }
+}
@@ -0,0 +1,33 @@
+use crate::logging;
+use std::{io, process::Command, thread};
+
+/// Starts `cmd`, a program and its arguments, without waiting for it. It is
+/// waited for on a thread of its own, so it does not linger as a zombie, and a
+/// failed exit is logged. An empty `cmd` does nothing.
+pub fn spawn_detached(cmd: &[String]) -> io::Result<()> {
+    let Some((program, args)) = cmd.split_first() else {
+        return Ok(());
+    };
+    let mut child = Command::new(program).args(args).spawn()?;
+    let program = program.clone();
+    thread::spawn(move || match child.wait() {
+        Ok(status) if !status.success() => {
+            logging::warning("command failed", &[("command", &program), ("status", &status)]);
+        }
+        Ok(_) => {}
+        Err(e) => logging::error("command failed", &[("command", &program), ("error", &e)]),
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spawns() {
+        assert!(spawn_detached(&["true".to_string(), "--ignored".to_string()]).is_ok());
+        assert!(spawn_detached(&[]).is_ok());
+        assert!(spawn_detached(&["/nonexistent/octobacillus-test".to_string()]).is_err());
+    }
+}
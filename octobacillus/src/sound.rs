@@ -0,0 +1,199 @@
+use crate::logging;
+use rodio::{Decoder, OutputStream, Sink};
+use std::{
+    fs::File,
+    io::BufReader,
+    path::PathBuf,
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Sender},
+    },
+    thread,
+    time::Duration,
+};
+
+/// Something the greeter can make a sound for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event {
+    Startup,
+    LoginSuccess,
+    AuthFailure,
+    Lockout,
+}
+
+/// The sound of one event.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cue {
+    pub file: Option<PathBuf>,
+    /// Multiplied with `[sound] volume`.
+    pub volume: f32,
+}
+
+impl Default for Cue {
+    fn default() -> Self {
+        Self { file: None, volume: 1.0 }
+    }
+}
+
+/// `[sound]` settings.
+#[derive(Clone, Debug)]
+pub struct Sound {
+    /// `[sound] enabled`, `--no-sound` turns it off
+    pub enabled: bool,
+    /// `[sound] volume`, 0.0 to 1.0
+    pub volume: f32,
+    /// `[sound] mute_button`, an on-screen toggle
+    pub mute_button: bool,
+    /// `[sound] startup`, cynager's info.probe `startup` sound if unset
+    pub startup: Cue,
+    /// `[sound] login`
+    pub login: Cue,
+    /// `[sound] failure`
+    pub failure: Cue,
+    /// `[sound] lockout`
+    pub lockout: Cue,
+}
+
+impl Default for Sound {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            volume: 1.0,
+            mute_button: true,
+            startup: Cue::default(),
+            login: Cue::default(),
+            failure: Cue::default(),
+            lockout: Cue::default(),
+        }
+    }
+}
+
+impl Sound {
+    pub fn cue(&self, event: Event) -> &Cue {
+        match event {
+            Event::Startup => &self.startup,
+            Event::LoginSuccess => &self.login,
+            Event::AuthFailure => &self.failure,
+            Event::Lockout => &self.lockout,
+        }
+    }
+}
+
+enum Command {
+    Play(Event),
+    Stop,
+    /// Answers once nothing is playing anymore.
+    Drain(Sender<()>),
+}
+
+/// Plays event sounds on a thread of its own. Without an audio device, or with
+/// sound disabled, every call is a no-op.
+#[derive(Clone)]
+pub struct Player {
+    tx: Option<Sender<Command>>,
+    muted: Arc<AtomicBool>,
+    /// Set by the audio thread once it tried to open the output.
+    device: Arc<OnceLock<bool>>,
+}
+
+impl Player {
+    pub fn new(sound: Sound) -> Self {
+        let muted = Arc::new(AtomicBool::new(false));
+        let device = Arc::new(OnceLock::new());
+        if !sound.enabled {
+            let _ = device.set(false);
+            return Self { tx: None, muted, device };
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let opened = device.clone();
+        thread::spawn(move || {
+            // The stream has to stay on this thread, it is not Send.
+            let (_stream, handle) = match OutputStream::try_default() {
+                Ok(output) => output,
+                Err(e) => {
+                    logging::warning("no audio, sounds are off", &[("error", &e)]);
+                    let _ = opened.set(false);
+                    return;
+                }
+            };
+            let _ = opened.set(true);
+
+            let mut playing: Vec<Sink> = Vec::new();
+            for command in rx {
+                playing.retain(|sink| !sink.empty());
+                let event = match command {
+                    Command::Play(event) => event,
+                    Command::Stop => {
+                        playing.drain(..).for_each(|sink| sink.stop());
+                        continue;
+                    }
+                    Command::Drain(done) => {
+                        playing.drain(..).for_each(|sink| sink.sleep_until_end());
+                        let _ = done.send(());
+                        continue;
+                    }
+                };
+                let cue = sound.cue(event);
+                let Some(path) = &cue.file else {
+                    continue;
+                };
+
+                let source = match File::open(path).map(BufReader::new) {
+                    Ok(file) => Decoder::new(file).map_err(|e| e.to_string()),
+                    Err(e) => Err(e.to_string()),
+                };
+                let sink = Sink::try_new(&handle).map_err(|e| e.to_string());
+                match (source, sink) {
+                    (Ok(source), Ok(sink)) => {
+                        sink.set_volume(sound.volume * cue.volume);
+                        sink.append(source);
+                        playing.push(sink);
+                    }
+                    (Err(e), _) | (_, Err(e)) => logging::error(&format!("{}: {e}", path.display()), &[]),
+                }
+            }
+        });
+
+        Self { tx: Some(tx), muted, device }
+    }
+
+    pub fn play(&self, event: Event) {
+        if let Some(tx) = &self.tx
+            && !self.muted()
+        {
+            // A failed send means there is no audio device, nothing to do about that.
+            let _ = tx.send(Command::Play(event));
+        }
+    }
+
+    /// Whether sounds can be heard: on, with an output device. `None` while the
+    /// device is still being opened. A mute toggle is pointless otherwise.
+    pub fn available(&self) -> Option<bool> {
+        self.device.get().copied()
+    }
+
+    pub fn muted(&self) -> bool {
+        self.muted.load(Ordering::Relaxed)
+    }
+
+    /// Waits up to `max` for what is playing to end, so quitting does not cut it off.
+    pub fn drain(&self, max: Duration) {
+        let Some(tx) = &self.tx else {
+            return;
+        };
+        let (done_tx, done) = mpsc::channel();
+        if tx.send(Command::Drain(done_tx)).is_ok() {
+            let _ = done.recv_timeout(max);
+        }
+    }
+
+    /// Muting also stops whatever is playing.
+    pub fn set_muted(&self, muted: bool) {
+        self.muted.store(muted, Ordering::Relaxed);
+        if muted && let Some(tx) = &self.tx {
+            let _ = tx.send(Command::Stop);
+        }
+    }
+}
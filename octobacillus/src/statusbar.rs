@@ -0,0 +1,159 @@
+use crate::{
+    i18n, idle,
+    sysinfo::{self, Charge},
+};
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Label, Orientation, glib};
+use std::{path::Path, time::Duration};
+
+/// `[status_bar]` settings.
+#[derive(Clone, Debug)]
+pub struct StatusBar {
+    /// `[status_bar] enabled`
+    pub enabled: bool,
+    /// `[status_bar] battery`, hidden anyway without a battery
+    pub battery: bool,
+    /// `[status_bar] network`
+    pub network: bool,
+    /// `[status_bar] hostname`
+    pub hostname: bool,
+    /// `[status_bar] kernel`
+    pub kernel: bool,
+    /// `[status_bar] uptime`
+    pub uptime: bool,
+    /// `[status_bar] interval` in seconds between updates
+    pub interval: Duration,
+}
+
+impl Default for StatusBar {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            battery: true,
+            network: true,
+            hostname: true,
+            kernel: false,
+            uptime: false,
+            interval: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Below this many percent a discharging battery gets the `low` class.
+const LOW_BATTERY: u8 = 15;
+
+fn item(bar: &GtkBox, id: &str) -> Label {
+    let label = Label::new(None);
+    label.set_widget_name(id);
+    label.add_css_class("status-item");
+    bar.append(&label);
+    label
+}
+
+fn show_battery(label: &Label) {
+    let batteries = sysinfo::batteries(Path::new(sysinfo::POWER_SUPPLY));
+    let Some(battery) = batteries.first() else {
+        label.set_visible(false);
+        return;
+    };
+    let charging = matches!(battery.charge, Charge::Charging | Charge::Full);
+    let n = battery.capacity;
+    label.set_visible(true);
+    label.set_text(&format!("{} {n}%", if charging { "⚡" } else { "🔋" }));
+    label.set_tooltip_text(Some(&if charging {
+        i18n::trf("battery_charging", &[("n", &n)])
+    } else {
+        i18n::trf("battery", &[("n", &n)])
+    }));
+    if !charging && n < LOW_BATTERY {
+        label.add_css_class("low");
+    } else {
+        label.remove_css_class("low");
+    }
+    if charging {
+        label.add_css_class("charging");
+    } else {
+        label.remove_css_class("charging");
+    }
+}
+
+fn show_network(label: &Label) {
+    let interfaces = sysinfo::interfaces(Path::new(sysinfo::NET));
+    // Wired first, it is what is used when both are up.
+    let up = interfaces
+        .iter()
+        .filter(|interface| interface.up)
+        .min_by_key(|interface| interface.wireless);
+    match up {
+        Some(interface) => {
+            label.set_text(if interface.wireless { "📶" } else { "🖧" });
+            label.set_tooltip_text(Some(&i18n::trf("network_connected", &[("interface", &interface.name)])));
+            label.remove_css_class("offline");
+        }
+        None => {
+            label.set_text("⚠");
+            label.set_tooltip_text(Some(&i18n::tr("network_offline")));
+            label.add_css_class("offline");
+        }
+    }
+}
+
+/// A bar of the enabled items, each a label with its own id, updated every `interval`.
+pub fn widget(status_bar: &StatusBar) -> Option<GtkBox> {
+    if !status_bar.enabled {
+        return None;
+    }
+    let bar = GtkBox::new(Orientation::Horizontal, 12);
+    bar.set_widget_name("status-bar");
+
+    let proc = Path::new(sysinfo::PROC);
+    if status_bar.hostname
+        && let Some(hostname) = sysinfo::hostname(proc)
+    {
+        item(&bar, "hostname").set_text(&hostname);
+    }
+    if status_bar.kernel
+        && let Some(kernel) = sysinfo::kernel(proc)
+    {
+        item(&bar, "kernel").set_text(&kernel);
+    }
+    let uptime = status_bar.uptime.then(|| item(&bar, "uptime"));
+    let network = status_bar.network.then(|| item(&bar, "network"));
+    let battery = status_bar.battery.then(|| item(&bar, "battery"));
+
+    let update = move || {
+        if let Some(label) = &uptime
+            && let Some(uptime) = sysinfo::uptime(proc)
+        {
+            label.set_text(&i18n::trf("uptime", &[("time", &sysinfo::short_duration(uptime))]));
+        }
+        if let Some(label) = &network {
+            show_network(label);
+        }
+        if let Some(label) = &battery {
+            show_battery(label);
+        }
+    };
+    {
+        // Also right away, and again with the texts of a switched language.
+        let bar_weak = bar.downgrade();
+        let update = update.clone();
+        i18n::on_change(move || {
+            if bar_weak.upgrade().is_some() {
+                update();
+            }
+        });
+    }
+
+    let bar_weak = bar.downgrade();
+    glib::timeout_add_local(status_bar.interval.max(Duration::from_secs(1)), move || {
+        if bar_weak.upgrade().is_none() {
+            return glib::ControlFlow::Break;
+        }
+        if !idle::blanked() {
+            update();
+        }
+        glib::ControlFlow::Continue
+    });
+    Some(bar)
+}
@@ -0,0 +1,174 @@
+use std::{fs, path::Path, time::Duration};
+
+pub const POWER_SUPPLY: &str = "/sys/class/power_supply";
+pub const NET: &str = "/sys/class/net";
+pub const PROC: &str = "/proc";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Charge {
+    Charging,
+    Discharging,
+    Full,
+    Unknown,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Battery {
+    pub name: String,
+    /// Percent, 0 to 100.
+    pub capacity: u8,
+    pub charge: Charge,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Interface {
+    pub name: String,
+    pub wireless: bool,
+    /// The link is up and carrying traffic.
+    pub up: bool,
+}
+
+fn read(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+/// Sorted subdirectory names of `dir`, nothing if it cannot be read.
+fn entries(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|e| e.ok()?.file_name().into_string().ok())
+        .collect();
+    names.sort();
+    names
+}
+
+/// The batteries under `power_supply`, e.g. `/sys/class/power_supply`. Mains
+/// adapters and the batteries of mice and keyboards are left out.
+pub fn batteries(power_supply: &Path) -> Vec<Battery> {
+    entries(power_supply)
+        .into_iter()
+        .filter_map(|name| {
+            let dir = power_supply.join(&name);
+            if read(&dir.join("type"))? != "Battery" || read(&dir.join("scope")).is_some_and(|s| s == "Device") {
+                return None;
+            }
+            let capacity = read(&dir.join("capacity"))?.parse::<u8>().ok()?.min(100);
+            let charge = match read(&dir.join("status")).as_deref() {
+                Some("Charging") => Charge::Charging,
+                Some("Discharging" | "Not charging") => Charge::Discharging,
+                Some("Full") => Charge::Full,
+                _ => Charge::Unknown,
+            };
+            Some(Battery { name, capacity, charge })
+        })
+        .collect()
+}
+
+/// The network interfaces under `net`, e.g. `/sys/class/net`, without loopback.
+pub fn interfaces(net: &Path) -> Vec<Interface> {
+    entries(net)
+        .into_iter()
+        .filter(|name| name != "lo")
+        .map(|name| {
+            let dir = net.join(&name);
+            Interface {
+                wireless: dir.join("wireless").exists(),
+                up: read(&dir.join("operstate")).is_some_and(|state| state == "up"),
+                name,
+            }
+        })
+        .collect()
+}
+
+/// The host name, from `proc`, e.g. `/proc`.
+pub fn hostname(proc: &Path) -> Option<String> {
+    read(&proc.join("sys/kernel/hostname")).filter(|name| !name.is_empty())
+}
+
+/// The kernel release, like `6.9.1-arch1-1`.
+pub fn kernel(proc: &Path) -> Option<String> {
+    read(&proc.join("sys/kernel/osrelease")).filter(|release| !release.is_empty())
+}
+
+pub fn uptime(proc: &Path) -> Option<Duration> {
+    let uptime = read(&proc.join("uptime"))?;
+    let seconds: f64 = uptime.split_whitespace().next()?.parse().ok()?;
+    Some(Duration::from_secs_f64(seconds.max(0.0)))
+}
+
+/// `2d 3h`, `3h 12m` or `12m`: the two largest units.
+pub fn short_duration(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    let (days, hours, minutes) = (minutes / (24 * 60), minutes / 60 % 24, minutes % 60);
+    if days > 0 {
+        format!("{days}d {hours}h")
+    } else if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else {
+        format!("{minutes}m")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn tree(files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("octobacillus-sysinfo-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for (path, content) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn reads_sysfs_and_proc() {
+        let root = tree(&[
+            ("power_supply/AC/type", "Mains\n"),
+            ("power_supply/BAT0/type", "Battery\n"),
+            ("power_supply/BAT0/capacity", "85\n"),
+            ("power_supply/BAT0/status", "Charging\n"),
+            ("power_supply/hidpp_battery_0/type", "Battery\n"),
+            ("power_supply/hidpp_battery_0/scope", "Device\n"),
+            ("power_supply/hidpp_battery_0/capacity", "10\n"),
+            ("net/lo/operstate", "unknown\n"),
+            ("net/enp3s0/operstate", "down\n"),
+            ("net/wlan0/operstate", "up\n"),
+            ("net/wlan0/wireless/status", ""),
+            ("proc/sys/kernel/hostname", "octopus\n"),
+            ("proc/sys/kernel/osrelease", "6.9.1-arch1-1\n"),
+            ("proc/uptime", "11520.42 40000.00\n"),
+        ]);
+
+        assert_eq!(
+            batteries(&root.join("power_supply")),
+            [Battery { name: "BAT0".to_string(), capacity: 85, charge: Charge::Charging }]
+        );
+        assert_eq!(
+            interfaces(&root.join("net")),
+            [
+                Interface { name: "enp3s0".to_string(), wireless: false, up: false },
+                Interface { name: "wlan0".to_string(), wireless: true, up: true },
+            ]
+        );
+        let proc = root.join("proc");
+        assert_eq!(hostname(&proc).as_deref(), Some("octopus"));
+        assert_eq!(kernel(&proc).as_deref(), Some("6.9.1-arch1-1"));
+        assert_eq!(uptime(&proc).map(short_duration).as_deref(), Some("3h 12m"));
+        assert!(batteries(&root.join("missing")).is_empty());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(short_duration(Duration::from_secs(59)), "0m");
+        assert_eq!(short_duration(Duration::from_secs(2 * 86400 + 3 * 3600 + 120)), "2d 3h");
+    }
+}
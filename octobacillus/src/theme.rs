@@ -0,0 +1,87 @@
+use crate::{config::Config, logging};
+use gtk4::{CssProvider, gdk, gio, prelude::*};
+use std::path::{Path, PathBuf};
+
+/// The style used when no theme is configured or the configured one is missing.
+const EMBEDDED: &str = include_str!("style.css");
+
+/// Finds the theme's stylesheet. A value with a `/` is a path to a CSS file or
+/// to a theme directory, anything else names a directory in `[paths] themes`.
+fn stylesheet(config: &Config) -> Option<PathBuf> {
+    let theme = config.theme.as_deref()?;
+    let path = if theme.contains('/') {
+        PathBuf::from(theme)
+    } else {
+        config.themes.join(theme)
+    };
+    Some(if path.is_dir() { path.join("theme.css") } else { path })
+}
+
+fn load(provider: &CssProvider, path: &Path) {
+    if path.is_file() {
+        provider.load_from_path(path);
+    } else {
+        logging::warning(&format!("theme {} not found, using the built-in style", path.display()), &[]);
+        provider.load_from_data(EMBEDDED);
+    }
+}
+
+/// Installs the theme for the whole display.
+///
+/// With `[theme] hot_reload` the theme's directory is watched and the style
+/// reloaded on every change; the returned monitor has to be kept alive for that.
+pub fn apply(config: &Config) -> Option<gio::FileMonitor> {
+    let display = gdk::Display::default()?;
+    let provider = CssProvider::new();
+    provider.connect_parsing_error(|_, section, error| {
+        let location = section.start_location();
+        let file = section.file().and_then(|f| f.path());
+        logging::warning(
+            &format!(
+                "{}:{}:{}: {error}",
+                file.as_deref().unwrap_or(Path::new("<theme>")).display(),
+                location.lines() + 1,
+                location.line_chars() + 1,
+            ),
+            &[],
+        );
+    });
+    gtk4::style_context_add_provider_for_display(
+        &display,
+        &provider,
+        gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION,
+    );
+
+    let Some(path) = stylesheet(config) else {
+        provider.load_from_data(EMBEDDED);
+        return None;
+    };
+    load(&provider, &path);
+
+    if !config.hot_reload {
+        return None;
+    }
+    // Watch the directory rather than the file, so assets and editors that
+    // save by renaming a temporary file trigger a reload too.
+    let dir = gio::File::for_path(path.parent()?);
+    let monitor = match dir.monitor_directory(gio::FileMonitorFlags::WATCH_MOVES, gio::Cancellable::NONE) {
+        Ok(monitor) => monitor,
+        Err(e) => {
+            logging::error(&format!("cannot watch {}: {e}", path.display()), &[]);
+            return None;
+        }
+    };
+    monitor.connect_changed(move |_, _, _, event| {
+        if matches!(
+            event,
+            gio::FileMonitorEvent::ChangesDoneHint
+                | gio::FileMonitorEvent::Created
+                | gio::FileMonitorEvent::Deleted
+                | gio::FileMonitorEvent::MovedIn
+                | gio::FileMonitorEvent::Renamed
+        ) {
+            load(&provider, &path);
+        }
+    });
+    Some(monitor)
+}
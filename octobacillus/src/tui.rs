@@ -0,0 +1,118 @@
+use crate::{
+    auth::{AuthFlow, Credentials, Refused, UiEvent},
+    banner,
+    config::{Choices, Config},
+    i18n,
+    ipc,
+    lockout::Lockout,
+    read_username_from_file,
+};
+use std::{
+    io::{self, Write},
+    sync::{Mutex, mpsc},
+    thread,
+    time::{Duration, Instant},
+};
+
+fn prompt(text: &str, default: &str) -> io::Result<String> {
+    if default.is_empty() {
+        print!("{text}: ");
+    } else {
+        print!("{text} [{default}]: ");
+    }
+    io::stdout().flush()?;
+
+    let mut line = String::new();
+    if io::stdin().read_line(&mut line)? == 0 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "stdin closed"));
+    }
+    let line = line.trim();
+    Ok(if line.is_empty() { default } else { line }.to_string())
+}
+
+fn wait_out_lockout(flow: &Mutex<AuthFlow>) {
+    loop {
+        let left = flow.lock().unwrap().locked_out(Instant::now());
+        let Some(left) = left else {
+            break;
+        };
+        print!("\r{} ", UiEvent::LockedOut(left).describe());
+        let _ = io::stdout().flush();
+        thread::sleep(Duration::from_secs(1).min(left));
+    }
+    println!();
+}
+
+/// Greets on the terminal, for when GTK cannot come up. Returns once a session was started.
+pub fn run(config: &Config) -> io::Result<()> {
+    let Some(backend) = ipc::backend(config) else {
+        return Err(io::Error::other("GREETD_SOCK is not set, try --demo"));
+    };
+    let flow = Mutex::new(AuthFlow::new(Lockout::new(
+        config.max_attempts,
+        config.lockout_base,
+        config.lockout_max,
+    )));
+    let default_user = read_username_from_file(&config.user_file).unwrap_or_default();
+    let default_cmd = config.command.join(" ");
+    let choices = Choices {
+        locale: config.language.clone().or_else(i18n::env_locale),
+        layout: config.keyboard.layouts.first().cloned(),
+    };
+
+    println!("octobacillus {}", env!("CARGO_PKG_VERSION"));
+    if let Some(text) = banner::text(&config.banner) {
+        println!("\n{text}\n");
+        if config.banner.must_acknowledge {
+            while !prompt(&i18n::tr("acknowledge_prompt"), "")?.eq_ignore_ascii_case(&i18n::tr("yes")) {}
+        }
+    }
+
+    loop {
+        let username = prompt(&i18n::tr("login_prompt"), &default_user)?;
+        if username.is_empty() {
+            continue;
+        }
+        let cmd = prompt(&i18n::tr("session_prompt"), &default_cmd)?;
+        let password = rpassword::read_password_from_tty(Some(&format!("{}: ", i18n::tr("password_prompt"))))?;
+
+        let cmd: Vec<String> = cmd.split_whitespace().map(str::to_string).collect();
+        let creds = Credentials {
+            username,
+            password,
+            env: config.session_env(&cmd, &choices),
+            cmd,
+        };
+
+        let login = flow.lock().unwrap().login(creds, Instant::now());
+        let first = match login {
+            Ok(request) => request,
+            Err(Refused::Busy) => unreachable!("the text greeter runs one conversation at a time"),
+            Err(Refused::LockedOut(_)) => {
+                wait_out_lockout(&flow);
+                continue;
+            }
+        };
+
+        let mut stream = match backend.connect() {
+            Ok(stream) => stream,
+            Err(e) => {
+                let event = flow.lock().unwrap().io_error(i18n::trf("connection_error", &[("error", &e)]));
+                ipc::log_event(&event);
+                println!("{}", event.describe());
+                continue;
+            }
+        };
+        let (tx, rx) = mpsc::channel();
+        ipc::run(&mut stream, first, &flow, &tx);
+        drop(tx);
+
+        for event in rx {
+            match event {
+                UiEvent::Started => return Ok(()),
+                UiEvent::LockedOut(_) => wait_out_lockout(&flow),
+                event => println!("{}", event.describe()),
+            }
+        }
+    }
+}